@@ -0,0 +1,158 @@
+// edit.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Times each command on generated trees of source files.
+//!
+//! Run with `cargo bench`. One tree has many small files, to show the
+//! per-file overhead, and the other has a few large files, to show the cost
+//! of editing big files. Each tree is run with the `current` and `preserve`
+//! year policies, since `preserve` reads each file's existing year first.
+//!
+//! To compare against another build, such as one from an earlier commit, set
+//! `LICENSESNIP_BASELINE` to the path of its binary. Both binaries then run
+//! on the same trees and their times are printed side by side.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// A tree of `files` Rust files of `lines` lines each.
+struct Tree {
+    name: &'static str,
+    files: usize,
+    lines: usize,
+}
+
+const TREES: [Tree; 2] = [
+    Tree {
+        name: "small files",
+        files: 2_000,
+        lines: 200,
+    },
+    Tree {
+        name: "large files",
+        files: 8,
+        lines: 500_000,
+    },
+];
+
+const YEAR_POLICIES: [&str; 2] = ["current", "preserve"];
+
+const COMMANDS: [(&str, &[&str]); 4] = [
+    ("add", &["--year", "2024"]),
+    ("check", &["check"]),
+    ("update", &["update", "--year", "2025"]),
+    ("remove", &["remove"]),
+];
+
+const RUNS: u32 = 6;
+
+const LICENSE: &str = "Copyright (c) %YEAR% Acme Inc.\n\nPermission is hereby granted, free of charge, to any person obtaining a copy\nof this software and associated documentation files.";
+
+fn main() {
+    let mut binaries = vec![("current", PathBuf::from(env!("CARGO_BIN_EXE_licensesnip")))];
+    if let Some(baseline) = env::var_os("LICENSESNIP_BASELINE") {
+        binaries.push(("baseline", PathBuf::from(baseline)));
+    }
+
+    for tree in &TREES {
+        for year_policy in YEAR_POLICIES {
+            println!(
+                "{}: {} files of {} lines, year_policy \"{}\", mean of {} runs",
+                tree.name, tree.files, tree.lines, year_policy, RUNS
+            );
+
+            let dirs: Vec<_> = binaries
+                .iter()
+                .map(|_| {
+                    let dir = tempfile::tempdir().expect("failed to create a temporary directory");
+                    generate_tree(dir.path(), tree, year_policy);
+                    dir
+                })
+                .collect();
+
+            // The binaries take turns, and which one goes first alternates, so
+            // both see the same system load and writeback from earlier runs
+            let mut totals = vec![[Duration::ZERO; COMMANDS.len()]; binaries.len()];
+            for run_index in 0..RUNS as usize {
+                for k in 0..binaries.len() {
+                    let j = (k + run_index) % binaries.len();
+                    let binary = &binaries[j].1;
+                    for (i, (_, args)) in COMMANDS.iter().enumerate() {
+                        totals[j][i] += run(binary, dirs[j].path(), args);
+                    }
+                }
+            }
+
+            for (i, (name, _)) in COMMANDS.iter().enumerate() {
+                let times: Vec<String> = binaries
+                    .iter()
+                    .zip(&totals)
+                    .map(|((label, _), total)| {
+                        let mean = total[i] / RUNS;
+                        format!("{}: {:>8.1} ms", label, mean.as_secs_f64() * 1000.0)
+                    })
+                    .collect();
+                println!("{:>8}: {}", name, times.join("   "));
+            }
+        }
+    }
+}
+
+fn generate_tree(root: &Path, tree: &Tree, year_policy: &str) {
+    fs::write(root.join(".licensesnip"), LICENSE).expect("failed to write .licensesnip");
+    fs::write(
+        root.join("licensesnip.config.jsonc"),
+        format!("{{ \"year_policy\": \"{}\" }}\n", year_policy),
+    )
+    .expect("failed to write licensesnip.config.jsonc");
+
+    let source: String = (0..tree.lines)
+        .map(|i| format!("pub fn function_{}() -> u32 {{ {} }}\n", i, i))
+        .collect();
+
+    for i in 0..tree.files {
+        let dir = root.join(format!("src/module_{}", i / 100));
+        fs::create_dir_all(&dir).expect("failed to create a source directory");
+        fs::write(dir.join(format!("file_{}.rs", i)), &source)
+            .expect("failed to write a source file");
+    }
+}
+
+fn run(binary: &Path, dir: &Path, args: &[&str]) -> Duration {
+    let start = Instant::now();
+    let status = Command::new(binary)
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .status()
+        .expect("failed to run licensesnip");
+    let elapsed = start.elapsed();
+
+    assert!(status.success(), "licensesnip {:?} failed", args);
+    elapsed
+}
@@ -0,0 +1,275 @@
+// cache.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{user_config_path, BASE_CONFIG, CFG_PATH};
+use crate::languages::LANGUAGES;
+use crate::license::License;
+
+pub const CACHE_PATH: &str = ".licensesnip-cache";
+
+/// A file that was last seen with a matching header.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct CacheEntry {
+    size: u64,
+    /// Modification time as seconds and nanoseconds
+    mtime: (i64, u32),
+    /// Hash of the header pattern the file matched
+    header: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct CacheFile {
+    fingerprint: String,
+    files: HashMap<String, CacheEntry>,
+}
+
+/// Remembers which files already have a matching header, so that unchanged
+/// files don't have to be read again.
+///
+/// The cache is thrown away whenever the config or `.licensesnip` changes.
+/// Each file is keyed by its path relative to the project root, and is only
+/// trusted while its size, modification time and header pattern stay the same.
+pub struct Cache {
+    path: PathBuf,
+    fingerprint: String,
+    files: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl Cache {
+    /// Loads the cache of the project at `root`, or starts an empty one if
+    /// there is none or it is out of date.
+    pub fn load(root: &Path, license: &License) -> Self {
+        let path = root.join(CACHE_PATH);
+        let fingerprint = fingerprint(root, license);
+
+        let files = match fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+        {
+            Some(cache_file) if cache_file.fingerprint == fingerprint => cache_file.files,
+            _ => HashMap::new(),
+        };
+
+        Self {
+            path,
+            fingerprint,
+            files: Mutex::new(files),
+        }
+    }
+
+    /// Whether a file is known to have a header matching `header_pattern`.
+    pub fn has_header(&self, path: &Path, file_path: &str, header_pattern: &str) -> bool {
+        let Some(entry) = cache_entry(path, header_pattern) else {
+            return false;
+        };
+
+        self.files.lock().unwrap().get(file_path) == Some(&entry)
+    }
+
+    /// Records that a file has a header matching `header_pattern`.
+    pub fn set_has_header(&self, path: &Path, file_path: &str, header_pattern: &str) {
+        let mut files = self.files.lock().unwrap();
+        match cache_entry(path, header_pattern) {
+            Some(entry) => files.insert(file_path.to_string(), entry),
+            None => files.remove(file_path),
+        };
+    }
+
+    /// Writes the cache to `.licensesnip-cache` in the project root.
+    pub fn save(self) -> io::Result<()> {
+        let cache_file = CacheFile {
+            fingerprint: self.fingerprint,
+            files: self.files.into_inner().unwrap(),
+        };
+        let json = serde_json::to_vec(&cache_file)?;
+
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let mut tmp = tempfile::Builder::new()
+            .prefix(".licensesnip-")
+            .tempfile_in(dir)?;
+        tmp.write_all(&json)?;
+        tmp.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+fn cache_entry(path: &Path, header_pattern: &str) -> Option<CacheEntry> {
+    let metadata = fs::metadata(path).ok()?;
+    let mtime = FileTime::from_last_modification_time(&metadata);
+
+    Some(CacheEntry {
+        size: metadata.len(),
+        mtime: (mtime.unix_seconds(), mtime.nanoseconds()),
+        header: hash(&[header_pattern.as_bytes()])[..16].to_string(),
+    })
+}
+
+/// Hashes everything that decides whether a header matches: each config
+/// layer, the language database, the root `.licensesnip` and the version of
+/// Licensesnip.
+fn fingerprint(root: &Path, license: &License) -> String {
+    let user_config = user_config_path()
+        .ok()
+        .and_then(|path| fs::read(path).ok())
+        .unwrap_or_default();
+    let project_config = fs::read(root.join(CFG_PATH)).unwrap_or_default();
+
+    hash(&[
+        env!("CARGO_PKG_VERSION").as_bytes(),
+        BASE_CONFIG.as_bytes(),
+        LANGUAGES.as_bytes(),
+        &user_config,
+        &project_config,
+        license.raw_text.as_bytes(),
+    ])
+}
+
+fn hash(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Prefix each part with its length, so that parts can't run together
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Copyright 2024 Acme";
+
+    fn license(text: &str) -> License {
+        License::new(String::from(text))
+    }
+
+    /// Records `a.rs` as having a header and saves the cache.
+    fn save_hit(root: &Path, license: &License) {
+        let cache = Cache::load(root, license);
+        cache.set_has_header(&root.join("a.rs"), "a.rs", HEADER);
+        cache.save().unwrap();
+    }
+
+    fn is_hit(root: &Path, license: &License) -> bool {
+        Cache::load(root, license).has_header(&root.join("a.rs"), "a.rs", HEADER)
+    }
+
+    /// Sets the mtime of a file to a fixed time, so that an edit can't land
+    /// on the same timestamp.
+    fn set_mtime(path: &Path, seconds: i64) {
+        filetime::set_file_mtime(path, FileTime::from_unix_time(seconds, 0)).unwrap();
+    }
+
+    #[test]
+    fn remembers_files_with_a_header() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "// Copyright 2024 Acme\n").unwrap();
+        let license = license(HEADER);
+
+        assert!(!is_hit(dir.path(), &license));
+        save_hit(dir.path(), &license);
+        assert!(is_hit(dir.path(), &license));
+
+        let cache = Cache::load(dir.path(), &license);
+        assert!(!cache.has_header(&dir.path().join("a.rs"), "a.rs", "Copyright 2025 Acme"));
+        assert!(!cache.has_header(&dir.path().join("a.rs"), "b.rs", HEADER));
+    }
+
+    #[test]
+    fn forgets_files_whose_size_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "// Copyright 2024 Acme\n").unwrap();
+        set_mtime(&path, 1_000_000_000);
+        let license = license(HEADER);
+        save_hit(dir.path(), &license);
+
+        fs::write(&path, "// Copyright 2024 Acme\nfn main() {}\n").unwrap();
+        set_mtime(&path, 1_000_000_000);
+        assert!(!is_hit(dir.path(), &license));
+    }
+
+    #[test]
+    fn forgets_files_whose_mtime_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "// Copyright 2024 Acme\n").unwrap();
+        set_mtime(&path, 1_000_000_000);
+        let license = license(HEADER);
+        save_hit(dir.path(), &license);
+
+        // Same size, different contents
+        fs::write(&path, "// Copyright 2025 Acme\n").unwrap();
+        set_mtime(&path, 1_000_000_001);
+        assert!(!is_hit(dir.path(), &license));
+    }
+
+    #[test]
+    fn forgets_everything_when_the_config_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "// Copyright 2024 Acme\n").unwrap();
+        let license = license(HEADER);
+        save_hit(dir.path(), &license);
+
+        fs::write(dir.path().join(CFG_PATH), "{ \"use_gitignore\": false }").unwrap();
+        assert!(!is_hit(dir.path(), &license));
+    }
+
+    #[test]
+    fn forgets_everything_when_the_license_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "// Copyright 2024 Acme\n").unwrap();
+        save_hit(dir.path(), &license(HEADER));
+
+        assert!(!is_hit(dir.path(), &license("Copyright 2024 Acme Inc.")));
+        assert!(is_hit(dir.path(), &license(HEADER)));
+    }
+
+    #[test]
+    fn forgets_files_that_are_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "// Copyright 2024 Acme\n").unwrap();
+        let license = license(HEADER);
+        save_hit(dir.path(), &license);
+
+        fs::remove_file(&path).unwrap();
+        let cache = Cache::load(dir.path(), &license);
+        cache.set_has_header(&path, "a.rs", HEADER);
+        assert!(cache.files.lock().unwrap().is_empty());
+    }
+}
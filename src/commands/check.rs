@@ -24,49 +24,90 @@
 
 use std::path::PathBuf;
 
-use chrono;
-use chrono::Datelike;
-
-use crate::frontend::{f_load_config, f_read_license, FileData, FileWalk};
-use crate::license::License;
+use crate::config::find_project_root;
+use crate::frontend::{
+    f_current_year, f_load_cache, f_load_config, f_print_outcome, f_read_license, f_save_cache,
+    FileData, FileOutcome, FileReport, FileWalk,
+};
+use crate::license::{header_file_path, License};
 
 use colored::*;
 
-use super::Commands;
+use super::{Commands, GlobalOptions};
 
-pub fn execute(args: Commands) {
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let jobs = options.jobs;
     let (verbose, file) = match args {
         Commands::Check { verbose, file } => (verbose, file.unwrap_or(PathBuf::from("."))),
         _ => panic!("Wrong command type"),
     };
-    let config = f_load_config();
-    let license = f_read_license();
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
+    let cache = f_load_cache(&config, &root, &license);
 
     let mut checked_files_count: u32 = 0;
 
-    let year = chrono::Utc::now().date().year();
+    let year = f_current_year(options.year);
 
-    let mut walk = FileWalk::new(file, config, license, year, verbose);
+    let walk = FileWalk::new(file, root, config, license, year, verbose, jobs);
 
-    for file_data in &mut walk {
+    let reports = walk.run(|file_data| {
         let FileData {
-            header_text,
+            file_path,
+            header_text: _,
+            header_pattern,
             formatted_license_lines: _,
             entry,
             file_type_config,
+            contents,
         } = file_data;
 
-        match License::check_file(&entry, &file_type_config, &header_text) {
+        let Some(cache) = &cache else {
+            return License::check_file(&entry, &file_type_config, &header_pattern, contents);
+        };
+        // A file in sidecar mode is compliant as long as its sidecar is
+        let header_path = header_file_path(entry.path(), &file_type_config);
+        if cache.has_header(&header_path, &file_path, header_pattern.as_str()) {
+            return Ok(true);
+        }
+
+        let result = License::check_file(&entry, &file_type_config, &header_pattern, contents);
+        if let Ok(true) = result {
+            cache.set_has_header(&header_path, &file_path, header_pattern.as_str());
+        }
+        result
+    });
+
+    f_save_cache(cache);
+
+    for FileReport { path, outcome } in reports {
+        let result = match outcome {
+            FileOutcome::Processed(result) => result,
+            FileOutcome::OrphanedSidecar(_) => {
+                println!(
+                    "(err) Orphaned sidecar, the file it belongs to doesn't exist - {}. \nDelete it, or run `licensesnip remove`.",
+                    path.display()
+                );
+                std::process::exit(1);
+            }
+            outcome => {
+                f_print_outcome(&outcome);
+                continue;
+            }
+        };
+
+        match result {
             Ok(r) => {
                 if r {
                     if verbose {
-                        println!("(ok) License header present - {}", entry.path().display());
+                        println!("(ok) License header present - {}", path.display());
                     }
                     checked_files_count += 1;
                 } else {
                     println!(
                         "(err) License header missing - {}. \nDid you forget to run `licensesnip`?",
-                        entry.path().display()
+                        path.display()
                     );
                     std::process::exit(1);
                 }
@@ -85,7 +126,7 @@ pub fn execute(args: Commands) {
 
     println!("{}", status_str_colored);
 
-    if walk.matched_filetypes_count == 0 {
+    if walk.matched_filetypes_count() == 0 {
         let warning = format!("{}\n\n{}\n\n{}", "⚠ No supported file types were found. You may need to add styling rules for your filetypes in your user/local config file. Run".yellow(), "licensesnip help", "for more info.".yellow());
 
         println!("{}", warning);
@@ -24,11 +24,12 @@
 
 use std::path::PathBuf;
 
-use chrono;
-use chrono::Datelike;
-
-use crate::frontend::{f_load_config, f_read_license, FileData, FileWalk};
-use crate::license::{AddToFileResult, License};
+use crate::config::find_project_root;
+use crate::frontend::{
+    f_current_year, f_load_cache, f_load_config, f_print_outcome, f_read_license, f_save_cache,
+    FileData, FileOutcome, FileReport, FileWalk,
+};
+use crate::license::{header_file_path, AddToFileResult, License};
 
 use colored::*;
 
@@ -36,39 +37,88 @@ use super::Cli;
 
 pub fn execute(args: Cli) {
     let verbose = args.verbose;
+    let year = args.year;
+    let jobs = args.jobs;
     let file = args.file.unwrap_or(PathBuf::from("."));
-    let config = f_load_config();
-    let license = f_read_license();
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
+    let cache = f_load_cache(&config, &root, &license);
 
     let mut changed_files_count: u32 = 0;
 
-    let year = chrono::Utc::now().date().year();
+    let year = f_current_year(year);
 
-    let mut walk = FileWalk::new(file, config, license, year, verbose);
+    let walk = FileWalk::new(file, root, config, license, year, verbose, jobs);
 
-    for file_data in &mut walk {
+    let reports = walk.run(|file_data| {
         let FileData {
+            file_path,
             header_text,
+            header_pattern,
             formatted_license_lines: _,
             entry,
             file_type_config,
+            contents,
         } = file_data;
 
-        match License::add_to_file(&entry, &file_type_config, &header_text) {
+        let Some(cache) = &cache else {
+            return License::add_to_file(
+                &entry,
+                &file_type_config,
+                &header_text,
+                &header_pattern,
+                contents,
+            );
+        };
+        // A file in sidecar mode is compliant as long as its sidecar is
+        let header_path = header_file_path(entry.path(), &file_type_config);
+        if cache.has_header(&header_path, &file_path, header_pattern.as_str()) {
+            return Ok(AddToFileResult::NoChange);
+        }
+
+        let result = License::add_to_file(
+            &entry,
+            &file_type_config,
+            &header_text,
+            &header_pattern,
+            contents,
+        );
+        if result.is_ok() {
+            cache.set_has_header(&header_path, &file_path, header_pattern.as_str());
+        }
+        result
+    });
+
+    f_save_cache(cache);
+
+    for FileReport { path, outcome } in reports {
+        let result = match outcome {
+            FileOutcome::Processed(result) => result,
+            outcome => {
+                f_print_outcome(&outcome);
+                continue;
+            }
+        };
+
+        match result {
             Ok(r) => {
                 match r {
                     AddToFileResult::Added => {
                         if verbose {
-                            println!("(ok) Added license header - {}", entry.path().display());
+                            println!("(ok) Added license header - {}", path.display());
+                        }
+                        changed_files_count += 1;
+                    }
+                    AddToFileResult::Relocated => {
+                        if verbose {
+                            println!("(ok) Moved license header to the top - {}", path.display());
                         }
                         changed_files_count += 1;
                     }
                     AddToFileResult::NoChange => {
                         if verbose {
-                            println!(
-                                "(skipped) Header already present - {}",
-                                entry.path().display()
-                            );
+                            println!("(skipped) Header already present - {}", path.display());
                         }
                     }
                 };
@@ -84,7 +134,7 @@ pub fn execute(args: Cli) {
 
     println!("{}", status_str_colored);
 
-    if walk.matched_filetypes_count == 0 {
+    if walk.matched_filetypes_count() == 0 {
         let warning = format!("{}\n\n{}\n\n{}", "⚠ No supported file types were found. You may need to add styling rules for your filetypes in your user/local config file. Run".yellow(), "licensesnip help", "for more info.".yellow());
 
         println!("{}", warning);
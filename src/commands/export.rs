@@ -0,0 +1,229 @@
+// export.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::find_project_root;
+use crate::frontend::{
+    f_current_year, f_load_config, f_now, f_read_license, relative_path, FileData, FileOutcome,
+    FileReport, FileWalk,
+};
+use crate::license::read_header;
+use crate::reuse::{license_text, ReuseInfo};
+use crate::sbom::{ExtractedLicense, SpdxDocument, SpdxFile};
+use crate::spdx::{expression_license_ids, validate_expression};
+use crate::write::{create_file, write_file};
+
+use colored::*;
+
+use super::{Commands, ExportFormat, GlobalOptions};
+
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let jobs = options.jobs;
+    let (file, format, output) = match args {
+        Commands::Export {
+            file,
+            format,
+            output,
+        } => (file.unwrap_or(PathBuf::from(".")), format, output),
+        _ => panic!("Wrong command type"),
+    };
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
+    let declared_license = config.spdx_id.clone();
+    if let Some(expression) = &declared_license {
+        if let Err(e) = validate_expression(expression) {
+            let error = format!(
+                "Error: \"{}\" isn't a valid SPDX license expression: {}",
+                expression, e
+            );
+            println!("{}", error.red());
+            std::process::exit(exitcode::CONFIG);
+        }
+    }
+
+    let year = f_current_year(options.year);
+    let created = f_now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let walk = FileWalk::new(file, root.clone(), config, license, year, false, jobs);
+
+    let reports = walk.run(|file_data| {
+        let FileData {
+            file_path,
+            header_text,
+            header_pattern,
+            formatted_license_lines: _,
+            entry,
+            file_type_config,
+            contents: header_contents,
+        } = file_data;
+
+        let contents = fs::read(entry.path())?;
+        let header = read_header(
+            entry.path(),
+            &file_type_config,
+            &header_text,
+            &header_pattern,
+            header_contents,
+        )?;
+
+        let info = header
+            .as_ref()
+            .map(|header| ReuseInfo::from_text(&header.text))
+            .unwrap_or_default();
+        let mut expressions = info.licenses;
+        // A header from a template without SPDX tags is under the project's license
+        if expressions.is_empty() && header.is_some_and(|header| header.is_current) {
+            expressions.extend(declared_license.clone());
+        }
+
+        Ok::<_, io::Error>(SpdxFile::new(
+            format!("./{}", file_path),
+            &contents,
+            license_ids(&expressions),
+            info.copyright,
+        ))
+    });
+
+    // Files with a file type configuration, by path
+    let mut analyzed = HashMap::new();
+    let mut unreadable = HashSet::new();
+    for FileReport { path, outcome } in reports {
+        // Messages go to standard error, so they don't end up in the document
+        match outcome {
+            FileOutcome::Processed(Ok(file)) => {
+                analyzed.insert(path, file);
+            }
+            FileOutcome::Processed(Err(e)) => {
+                eprintln!("{}", format!("Error: {}: {}", path.display(), e).red());
+                unreadable.insert(path);
+            }
+            FileOutcome::Failed(reason) => eprintln!("{}", reason.red()),
+            FileOutcome::Skipped(_) | FileOutcome::OrphanedSidecar(_) => {}
+        }
+    }
+
+    // Every file is listed, so that the verification code covers the whole
+    // package. Files that can't hold a header have no license information.
+    let mut files = Vec::new();
+    for path in walk.all_files() {
+        if let Some(file) = analyzed.remove(&path) {
+            files.push(file);
+            continue;
+        }
+        match fs::read(&path) {
+            Ok(contents) => files.push(SpdxFile::new(
+                format!("./{}", relative_path(&root, &path)),
+                &contents,
+                Vec::new(),
+                Vec::new(),
+            )),
+            Err(e) if !unreadable.contains(&path) => {
+                eprintln!("{}", format!("Error: {}: {}", path.display(), e).red())
+            }
+            Err(_) => {}
+        }
+    }
+
+    let mut extracted_ids: Vec<&str> = files
+        .iter()
+        .flat_map(|file| &file.licenses)
+        .map(String::as_str)
+        .filter(|id| id.starts_with("LicenseRef-"))
+        .collect();
+    extracted_ids.sort_unstable();
+    extracted_ids.dedup();
+    let extracted_licenses = extracted_ids
+        .into_iter()
+        .map(|id| ExtractedLicense {
+            id: id.to_string(),
+            text: license_text(&root, id),
+        })
+        .collect();
+
+    let document = SpdxDocument {
+        name: root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("project")),
+        created,
+        declared_license,
+        files,
+        extracted_licenses,
+    };
+    let text = match format {
+        ExportFormat::SpdxJson => document.to_json(),
+        ExportFormat::SpdxTv => document.to_tag_value(),
+    };
+
+    match output {
+        Some(output) => {
+            if let Err(e) = write_output(&output, &text) {
+                let error = format!("Error: Couldn't write {}: {}", output.display(), e);
+                println!("{}", error.red());
+                std::process::exit(exitcode::IOERR);
+            }
+            let status_str = format!(
+                "✔ Wrote {} files to {}.",
+                document.files.len(),
+                output.display()
+            );
+            println!("{}", status_str.green());
+        }
+        None => print!("{}", text),
+    }
+
+    std::process::exit(exitcode::OK);
+}
+
+/// Gets the license identifiers in some license expressions, leaving out ones
+/// that aren't valid and the exceptions added with `WITH`, which aren't
+/// licenses of their own.
+fn license_ids(expressions: &[String]) -> Vec<String> {
+    let mut ids = Vec::<String>::new();
+    for expression in expressions {
+        if validate_expression(expression).is_err() {
+            continue;
+        }
+        for id in expression_license_ids(expression) {
+            if !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+fn write_output(path: &Path, text: &str) -> io::Result<()> {
+    if path.exists() {
+        write_file(path, text.as_bytes(), false)
+    } else {
+        create_file(path, text.as_bytes())
+    }
+}
@@ -0,0 +1,158 @@
+// init.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use colored::*;
+
+use crate::config::find_project_root;
+use crate::license::LICENSE_PATH;
+use crate::licenses::{bundled_license, fill_in_template, BUNDLED_LICENSES, HOLDER_PLACEHOLDER};
+
+use super::{Commands, GlobalOptions};
+
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let year = options.year;
+    let (license, holder, force, list) = match args {
+        Commands::Init {
+            license,
+            holder,
+            force,
+            list,
+        } => (license, holder, force, list),
+        _ => panic!("Wrong command type"),
+    };
+
+    if list {
+        println!("Bundled licenses:");
+        for (id, _) in BUNDLED_LICENSES {
+            println!("  {}", id);
+        }
+        std::process::exit(exitcode::OK);
+    }
+
+    let license = license.unwrap_or_default();
+    let (id, template) = match bundled_license(&license) {
+        Some(l) => l,
+        None => {
+            let error = format!(
+                "Error: No bundled license for \"{}\". Run `licensesnip init --list` to see the available licenses.",
+                license
+            );
+            println!("{}", error.red());
+            std::process::exit(exitcode::USAGE);
+        }
+    };
+
+    let holder = match holder {
+        Some(h) => h,
+        None if template.contains(HOLDER_PLACEHOLDER) => {
+            let error = format!(
+                "Error: The {} license names a copyright holder. Pass one with --holder.",
+                id
+            );
+            println!("{}", error.red());
+            std::process::exit(exitcode::USAGE);
+        }
+        None => String::new(),
+    };
+
+    let path = license_path(Path::new("."));
+    match write_license(&path, &fill_in_template(template, &holder, year), force) {
+        Ok(_) => {
+            let status_str = format!("✔ Created {} with the {} license.", path.display(), id);
+            println!("{}", status_str.green());
+            std::process::exit(exitcode::OK);
+        }
+        Err(InitErr::AlreadyExists) => {
+            let error = format!(
+                "Error: {} already exists. Pass --force to overwrite it.",
+                path.display()
+            );
+            println!("{}", error.red());
+            std::process::exit(exitcode::CANTCREAT);
+        }
+        Err(InitErr::WriteErr(e)) => {
+            println!(
+                "{}",
+                format!("Error: Failed to write {}: {}", path.display(), e).red()
+            );
+            std::process::exit(exitcode::IOERR);
+        }
+    }
+}
+
+enum InitErr {
+    AlreadyExists,
+    WriteErr(io::Error),
+}
+
+/// Gets where the license template goes, which is the root of the project
+/// that `start` is in, as for every other command.
+fn license_path(start: &Path) -> PathBuf {
+    find_project_root(start).join(LICENSE_PATH)
+}
+
+/// Writes a license template to `path`, unless there's a file there already
+/// and `force` isn't set.
+fn write_license(path: &Path, text: &str, force: bool) -> Result<(), InitErr> {
+    if path.exists() && !force {
+        return Err(InitErr::AlreadyExists);
+    }
+    fs::write(path, text).map_err(InitErr::WriteErr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_to_the_project_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+
+        assert_eq!(license_path(&root.join("src/bin")), root.join(LICENSE_PATH));
+        assert_eq!(license_path(&root), root.join(LICENSE_PATH));
+    }
+
+    #[test]
+    fn overwrites_only_when_forced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LICENSE_PATH);
+
+        assert!(write_license(&path, "first", false).is_ok());
+        assert!(matches!(
+            write_license(&path, "second", false),
+            Err(InitErr::AlreadyExists)
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        assert!(write_license(&path, "third", true).is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+    }
+}
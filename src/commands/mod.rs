@@ -25,11 +25,15 @@
 pub mod check;
 pub mod config;
 pub mod default;
+pub mod export;
+pub mod init;
 pub mod remove;
+pub mod reuse;
+pub mod update;
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{ArgEnum, Parser, Subcommand};
 
 // Note: this requires the `derive` feature
 #[derive(Parser)]
@@ -41,11 +45,25 @@ pub struct Cli {
     // Whether to display extra detailed output
     #[clap(short, long)]
     pub verbose: bool,
+    /// Use this year as the current year
+    #[clap(long, global = true)]
+    pub year: Option<i32>,
+    /// Number of threads to use, one per CPU by default
+    #[clap(short, long, global = true)]
+    pub jobs: Option<usize>,
 
     #[clap(subcommand)]
     pub command: Option<Commands>,
 }
 
+/// Options given before or after the subcommand, which every subcommand that
+/// walks files takes.
+#[derive(Clone, Copy)]
+pub struct GlobalOptions {
+    pub year: Option<i32>,
+    pub jobs: Option<usize>,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Get path to config file
@@ -55,6 +73,22 @@ pub enum Commands {
         #[clap(short, long)]
         directory: bool,
     },
+    /// Create a .licensesnip file from a bundled license
+    #[clap(arg_required_else_help = true)]
+    Init {
+        /// SPDX identifier of the license, e.g. MIT or Apache-2.0
+        #[clap(short, long, required_unless_present = "list")]
+        license: Option<String>,
+        /// Name of the copyright holder
+        #[clap(long)]
+        holder: Option<String>,
+        /// Overwrite an existing .licensesnip file
+        #[clap(short, long)]
+        force: bool,
+        /// List the bundled licenses
+        #[clap(long)]
+        list: bool,
+    },
     /// Remove all license headers from directory files
     #[clap(arg_required_else_help = false)]
     Remove {
@@ -73,4 +107,45 @@ pub enum Commands {
         #[clap(short, long)]
         verbose: bool,
     },
+    /// Check that every file has copyright and license information, as the REUSE specification requires
+    #[clap(arg_required_else_help = false)]
+    Reuse {
+        /// The file(s) to check
+        file: Option<PathBuf>,
+        /// Display more information
+        #[clap(short, long)]
+        verbose: bool,
+        /// Add REUSE.toml annotations for files that can't have a license header
+        #[clap(short, long)]
+        generate: bool,
+    },
+    /// Write an SPDX document listing each file with its checksums, license and copyright
+    #[clap(arg_required_else_help = false)]
+    Export {
+        /// The file(s) to list
+        file: Option<PathBuf>,
+        /// Format of the document
+        #[clap(short, long, arg_enum, default_value = "spdx-json")]
+        format: ExportFormat,
+        /// File to write the document to, instead of standard output
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replace outdated license headers with the current one
+    #[clap(arg_required_else_help = false)]
+    Update {
+        /// The file(s) to update the license header in
+        file: Option<PathBuf>,
+        /// Display more information
+        #[clap(short, long)]
+        verbose: bool,
+    },
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum ExportFormat {
+    /// SPDX 2.3 JSON
+    SpdxJson,
+    /// SPDX 2.3 tag-value
+    SpdxTv,
 }
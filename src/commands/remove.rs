@@ -22,46 +22,76 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::path::PathBuf;
+use std::{fs, path::PathBuf};
 
-use chrono;
-use chrono::Datelike;
-
-use crate::frontend::{f_load_config, f_read_license, FileData, FileWalk};
+use crate::config::find_project_root;
+use crate::frontend::{
+    f_current_year, f_load_config, f_print_outcome, f_read_license, FileData, FileOutcome,
+    FileReport, FileWalk,
+};
 use crate::license::{License, RemoveFromFileResult};
 
 use colored::*;
 
-use super::Commands;
+use super::{Commands, GlobalOptions};
 
-pub fn execute(args: Commands) {
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let jobs = options.jobs;
     let (verbose, file) = match args {
         Commands::Remove { verbose, file } => (verbose, file.unwrap_or(PathBuf::from("."))),
         _ => panic!("Wrong command type"),
     };
-    let config = f_load_config();
-    let license = f_read_license();
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
 
     let mut changed_files_count: u32 = 0;
 
-    let year = chrono::Utc::now().date().year();
+    let year = f_current_year(options.year);
 
-    let mut walk = FileWalk::new(file, config, license, year, verbose);
+    let walk = FileWalk::new(file, root, config, license, year, verbose, jobs);
 
-    for file_data in &mut walk {
+    let reports = walk.run(|file_data| {
         let FileData {
-            header_text,
+            file_path: _,
+            header_text: _,
+            header_pattern,
             formatted_license_lines: _,
             entry,
             file_type_config,
+            contents,
         } = file_data;
 
-        match License::remove_from_file(&entry, &file_type_config, &header_text) {
+        License::remove_from_file(&entry, &file_type_config, &header_pattern, contents)
+    });
+
+    for FileReport { path, outcome } in reports {
+        let result = match outcome {
+            FileOutcome::Processed(result) => result,
+            FileOutcome::OrphanedSidecar(_) => {
+                match fs::remove_file(&path) {
+                    Ok(_) => {
+                        if verbose {
+                            println!("(ok) Removed orphaned sidecar - {}", path.display())
+                        }
+                        changed_files_count += 1;
+                    }
+                    Err(e) => println!("{:?}", e),
+                }
+                continue;
+            }
+            outcome => {
+                f_print_outcome(&outcome);
+                continue;
+            }
+        };
+
+        match result {
             Ok(r) => {
                 match r {
                     RemoveFromFileResult::Removed => {
                         if verbose {
-                            println!("(ok) Removed license header - {}", entry.path().display())
+                            println!("(ok) Removed license header - {}", path.display())
                         }
                         changed_files_count += 1;
                     }
@@ -69,7 +99,7 @@ pub fn execute(args: Commands) {
                         if verbose {
                             println!(
                                 "(skipped) No matching header to remove - {}",
-                                entry.path().display()
+                                path.display()
                             )
                         }
                     }
@@ -89,7 +119,7 @@ pub fn execute(args: Commands) {
 
     println!("{}", status_str_colored);
 
-    if walk.matched_filetypes_count == 0 {
+    if walk.matched_filetypes_count() == 0 {
         let warning = format!("{}\n\n{}\n\n{}", "⚠ No supported file types were found. You may need to add styling rules for your filetypes in your user/local config file. Run".yellow(), "licensesnip help", "for more info.".yellow());
 
         println!("{}", warning);
@@ -0,0 +1,328 @@
+// reuse.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::{find_project_root, FileMode, HeaderMode};
+use crate::frontend::{
+    f_current_date, f_current_year, f_load_config, f_read_license, f_sidecar_license,
+    relative_path, FileOutcome, FileWalk,
+};
+use crate::license::SIDECAR_SUFFIX;
+use crate::reuse::{
+    file_info, license_texts, load_reuse_toml, render_annotation, ReuseToml, LICENSES_DIR,
+    REUSE_TOML_PATH,
+};
+use crate::spdx::{expression_ids, validate_expression};
+use crate::write::{create_file, write_file};
+
+use colored::*;
+
+use super::{Commands, GlobalOptions};
+
+/// Names of files that REUSE counts as license texts themselves.
+const LICENSE_FILE_NAMES: [&str; 3] = ["LICENSE", "LICENCE", "COPYING"];
+
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let jobs = options.jobs;
+    let (verbose, file, generate) = match args {
+        Commands::Reuse {
+            verbose,
+            file,
+            generate,
+        } => (verbose, file.unwrap_or(PathBuf::from(".")), generate),
+        _ => panic!("Wrong command type"),
+    };
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
+    let reuse_toml = f_load_reuse_toml(&root);
+
+    let year = f_current_year(options.year);
+
+    // The SPDX tags for new annotations
+    let spdx_lines = generate.then(|| {
+        let spdx_license = f_sidecar_license(&config);
+        match spdx_license.get_shared_lines(&year.to_string(), &f_current_date(), &config.variables)
+        {
+            Ok(lines) => lines,
+            Err(e) => {
+                println!("{}", format!("Error in copyright_holder: {}", e).red());
+                std::process::exit(exitcode::CONFIG);
+            }
+        }
+    });
+    let spdx_id = config.spdx_id.clone();
+    let spdx_headers = config.header_mode == HeaderMode::Spdx;
+
+    let walk = FileWalk::new(file, root.clone(), config, license, year, false, jobs);
+
+    // Files that `licensesnip` can give a header, and how each holds it
+    let commentable: HashMap<PathBuf, FileMode> = walk
+        .run(|file_data| file_data.file_type_config.mode)
+        .into_iter()
+        .filter_map(|report| match report.outcome {
+            FileOutcome::Processed(mode) => Some((report.path, mode)),
+            _ => None,
+        })
+        .collect();
+
+    let mut checked_files_count: u32 = 0;
+    let mut missing = Vec::<(PathBuf, String, &str)>::new();
+    // Each license expression used, and a file that uses it
+    let mut expressions = BTreeMap::<String, PathBuf>::new();
+
+    for path in walk.all_files() {
+        let file_path = relative_path(&root, &path);
+        if is_exempt(&path, &file_path) {
+            continue;
+        }
+        checked_files_count += 1;
+
+        let info = match file_info(
+            &path,
+            &file_path,
+            commentable.get(&path).copied().unwrap_or_default(),
+            reuse_toml.as_ref(),
+        ) {
+            Ok(info) => info,
+            Err(e) => {
+                let error = format!("Error: Couldn't read {}: {}", path.display(), e);
+                println!("{}", error.red());
+                continue;
+            }
+        };
+
+        for expression in &info.licenses {
+            expressions
+                .entry(expression.clone())
+                .or_insert_with(|| path.clone());
+        }
+
+        let what = match (info.copyright.is_empty(), info.licenses.is_empty()) {
+            (false, false) => {
+                if verbose {
+                    println!(
+                        "(ok) Copyright and license information present - {}",
+                        path.display()
+                    );
+                }
+                continue;
+            }
+            (true, true) => "copyright and license information",
+            (true, false) => "copyright information",
+            (false, true) => "license information",
+        };
+        missing.push((path, file_path, what));
+    }
+
+    if let Some(spdx_lines) = spdx_lines {
+        let (annotated, rest): (Vec<_>, Vec<_>) = missing
+            .into_iter()
+            .partition(|(path, _, _)| !commentable.contains_key(path));
+        missing = rest;
+
+        if !annotated.is_empty() {
+            let file_paths: Vec<String> = annotated
+                .iter()
+                .map(|(_, file_path, _)| file_path.clone())
+                .collect();
+            let annotation = render_annotation(&file_paths, &spdx_lines);
+            if let Err(e) = add_annotation(&root, &annotation) {
+                let error = format!("Error: Couldn't write {}: {}", REUSE_TOML_PATH, e);
+                println!("{}", error.red());
+                std::process::exit(exitcode::IOERR);
+            }
+
+            if verbose {
+                for (path, _, _) in &annotated {
+                    println!("(ok) Added to {} - {}", REUSE_TOML_PATH, path.display());
+                }
+            }
+            println!(
+                "{}",
+                format!("✔ Added {} files to {}.", annotated.len(), REUSE_TOML_PATH).green()
+            );
+
+            if let (Some(spdx_id), Some((path, _, _))) = (spdx_id, annotated.first()) {
+                expressions.entry(spdx_id).or_insert_with(|| path.clone());
+            }
+        }
+    }
+
+    let mut errors_count = missing.len();
+
+    for (path, _, what) in &missing {
+        let hint = if commentable.contains_key(path) && spdx_headers {
+            "Run `licensesnip` to add a header."
+        } else if commentable.contains_key(path) {
+            "Set \"header_mode\" to \"spdx\" in your config and run `licensesnip` to add a header."
+        } else {
+            "Run `licensesnip reuse --generate` to add it to REUSE.toml."
+        };
+        println!("(err) Missing {} - {}. {}", what, path.display(), hint);
+    }
+
+    let texts = license_texts(&root);
+    for (expression, path) in &expressions {
+        if let Err(e) = validate_expression(expression) {
+            println!(
+                "(err) Invalid SPDX license expression \"{}\" in {}: {}",
+                expression,
+                path.display(),
+                e
+            );
+            errors_count += 1;
+            continue;
+        }
+
+        // Licenses in other documents don't need a text here
+        for id in expression_ids(expression)
+            .into_iter()
+            .filter(|id| !id.contains(':'))
+        {
+            if !texts.contains(id) {
+                println!(
+                    "(err) No license text for {}, which {} uses. Add it as {}/{}.txt.",
+                    id,
+                    path.display(),
+                    LICENSES_DIR,
+                    id
+                );
+                errors_count += 1;
+            }
+        }
+    }
+
+    if errors_count > 0 {
+        std::process::exit(1);
+    }
+
+    let status_str = format!(
+        "✔ Copyright and license information present in all {} files.",
+        checked_files_count
+    );
+    println!("{}", status_str.green());
+
+    std::process::exit(exitcode::OK);
+}
+
+fn f_load_reuse_toml(root: &Path) -> Option<ReuseToml> {
+    match load_reuse_toml(root) {
+        Ok(reuse_toml) => reuse_toml,
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).red());
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+}
+
+/// Whether REUSE leaves a file out of its checks: license texts, sidecars and
+/// `REUSE.toml` itself. License texts are named like `LICENSE`, `LICENSE.md`
+/// or `LICENSE-MIT`, as in the REUSE spec.
+fn is_exempt(path: &Path, file_path: &str) -> bool {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let is_license_text = LICENSE_FILE_NAMES.iter().any(|name| {
+        file_name
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '-']))
+    });
+
+    file_path.starts_with(&format!("{}/", LICENSES_DIR))
+        || file_path == REUSE_TOML_PATH
+        || file_name.ends_with(SIDECAR_SUFFIX)
+        || is_license_text
+}
+
+/// Adds an annotation to the end of `REUSE.toml`, creating it if needed.
+fn add_annotation(root: &Path, annotation: &str) -> io::Result<()> {
+    let path = root.join(REUSE_TOML_PATH);
+    match fs::read(&path) {
+        Ok(mut text) => {
+            if !text.is_empty() && !text.ends_with(b"\n") {
+                text.push(b'\n');
+            }
+            text.push(b'\n');
+            text.extend_from_slice(annotation.as_bytes());
+            write_file(&path, &text, false)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            create_file(&path, format!("version = 1\n\n{}", annotation).as_bytes())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exempt(file_path: &str) -> bool {
+        is_exempt(Path::new(file_path), file_path)
+    }
+
+    #[test]
+    fn exempts_license_texts() {
+        for file_path in [
+            "LICENSE",
+            "LICENCE",
+            "COPYING",
+            "LICENSE.md",
+            "LICENSE-MIT",
+            "LICENSE-APACHE.txt",
+            "COPYING.LESSER",
+            "docs/LICENSE.txt",
+            "LICENSES/MIT.txt",
+        ] {
+            assert!(exempt(file_path), "{}", file_path);
+        }
+    }
+
+    #[test]
+    fn exempts_sidecars_and_reuse_toml() {
+        assert!(exempt("logo.png.license"));
+        assert!(exempt(REUSE_TOML_PATH));
+    }
+
+    #[test]
+    fn checks_other_files() {
+        for file_path in [
+            "main.rs",
+            "LICENSEE.md",
+            "COPYING_NOTES",
+            "license.md",
+            "src/licenses.rs",
+            "docs/REUSE.toml",
+        ] {
+            assert!(!exempt(file_path), "{}", file_path);
+        }
+    }
+}
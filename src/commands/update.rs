@@ -0,0 +1,140 @@
+// update.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::path::PathBuf;
+
+use crate::config::find_project_root;
+use crate::frontend::{
+    f_current_year, f_load_config, f_print_outcome, f_read_license, FileData, FileOutcome,
+    FileReport, FileWalk,
+};
+use crate::license::{License, UpdateFileResult};
+
+use colored::*;
+
+use super::{Commands, GlobalOptions};
+
+pub fn execute(args: Commands, options: GlobalOptions) {
+    let jobs = options.jobs;
+    let (verbose, file) = match args {
+        Commands::Update { verbose, file } => (verbose, file.unwrap_or(PathBuf::from("."))),
+        _ => panic!("Wrong command type"),
+    };
+    let root = find_project_root(&file);
+    let config = f_load_config(&root);
+    let license = f_read_license(&config, &root);
+
+    let mut updated_files_count: u32 = 0;
+    let mut unchanged_files_count: u32 = 0;
+    let mut unrecognized_files_count: u32 = 0;
+
+    let year = f_current_year(options.year);
+
+    let walk = FileWalk::new(file, root, config, license, year, verbose, jobs);
+
+    let reports = walk.run(|file_data| {
+        let FileData {
+            file_path: _,
+            header_text,
+            header_pattern,
+            formatted_license_lines: _,
+            entry,
+            file_type_config,
+            contents,
+        } = file_data;
+
+        License::update_file(
+            &entry,
+            &file_type_config,
+            &header_text,
+            &header_pattern,
+            contents,
+        )
+    });
+
+    for FileReport { path, outcome } in reports {
+        let result = match outcome {
+            FileOutcome::Processed(result) => result,
+            outcome => {
+                f_print_outcome(&outcome);
+                continue;
+            }
+        };
+
+        match result {
+            Ok(r) => {
+                match r {
+                    UpdateFileResult::Updated => {
+                        if verbose {
+                            println!("(ok) Updated license header - {}", path.display());
+                        }
+                        updated_files_count += 1;
+                    }
+                    UpdateFileResult::NoChange => {
+                        if verbose {
+                            println!("(skipped) Header already up to date - {}", path.display());
+                        }
+                        unchanged_files_count += 1;
+                    }
+                    UpdateFileResult::Unrecognized => {
+                        if verbose {
+                            println!(
+                                "(skipped) No existing license header found - {}",
+                                path.display()
+                            );
+                        }
+                        unrecognized_files_count += 1;
+                    }
+                };
+            }
+            Err(e) => {
+                println!("{:?}", e)
+            }
+        }
+    }
+
+    let status_str = format!(
+        "✔ Updated license header in {} files, {} already up to date.",
+        updated_files_count, unchanged_files_count
+    );
+    let status_str_colored = status_str.green();
+
+    println!("{}", status_str_colored);
+
+    if unrecognized_files_count > 0 {
+        let warning = format!(
+            "⚠ No existing license header was recognized in {} files. Run `licensesnip` to add one.",
+            unrecognized_files_count
+        );
+        println!("{}", warning.yellow());
+    }
+
+    if walk.matched_filetypes_count() == 0 {
+        let warning = format!("{}\n\n{}\n\n{}", "⚠ No supported file types were found. You may need to add styling rules for your filetypes in your user/local config file. Run".yellow(), "licensesnip help", "for more info.".yellow());
+
+        println!("{}", warning);
+    }
+
+    std::process::exit(exitcode::OK);
+}
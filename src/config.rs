@@ -23,13 +23,17 @@
 // SOFTWARE.
 
 use directories::ProjectDirs;
-
+use globset::{GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::clone::Clone;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::{fmt, fs};
 
+use crate::languages::{builtin_languages, Language};
+use crate::license::LICENSE_PATH;
+
 pub static DEFAULT_CONFIG: &str = include_str!("default-config.jsonc");
 pub static BASE_CONFIG: &str = include_str!("base-config.jsonc");
 pub const CFG_PATH: &str = "licensesnip.config.jsonc";
@@ -48,25 +52,596 @@ pub struct FileTypeConfig {
     pub enable: bool,
     #[serde(default = "get_true")]
     pub skip_shebang_line: bool,
+    /// What a shebang line looks like, when `skip_shebang_line` is on
+    #[serde(default = "default_shebang")]
+    pub shebang: LinePatterns,
+    /// Leading lines that stay above the header, such as `<?php`
+    #[serde(default)]
+    pub preamble: LinePatterns,
+    #[serde(default)]
+    pub placement: Placement,
+    /// Whether edited files keep their modification time
+    #[serde(default)]
+    pub preserve_mtime: bool,
+    #[serde(default)]
+    pub mode: FileMode,
+    /// Type of the cell holding the header, in notebook mode
+    #[serde(default)]
+    pub notebook_cell: NotebookCell,
+}
+
+/// `FileTypeConfig` with every field optional, for settings that are applied
+/// on top of a file type's own settings.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct PartialFileTypeConfig {
+    pub before_block: Option<String>,
+    pub after_block: Option<String>,
+    pub before_line: Option<String>,
+    pub after_line: Option<String>,
+    pub enable: Option<bool>,
+    pub skip_shebang_line: Option<bool>,
+    pub shebang: Option<LinePatterns>,
+    pub preamble: Option<LinePatterns>,
+    pub placement: Option<Placement>,
+    pub preserve_mtime: Option<bool>,
+    pub mode: Option<FileMode>,
+    pub notebook_cell: Option<NotebookCell>,
+}
+
+impl Default for FileTypeConfig {
+    fn default() -> Self {
+        Self {
+            before_block: String::new(),
+            after_block: String::new(),
+            before_line: String::new(),
+            after_line: String::new(),
+            enable: true,
+            skip_shebang_line: true,
+            shebang: default_shebang(),
+            preamble: LinePatterns::default(),
+            placement: Placement::default(),
+            preserve_mtime: false,
+            mode: FileMode::default(),
+            notebook_cell: NotebookCell::default(),
+        }
+    }
+}
+
+impl FileTypeConfig {
+    pub fn assign_partial(target: &Self, source: &PartialFileTypeConfig) -> Self {
+        let mut new = target.clone();
+
+        if let Some(before_block) = &source.before_block {
+            new.before_block = before_block.to_string();
+        }
+        if let Some(after_block) = &source.after_block {
+            new.after_block = after_block.to_string();
+        }
+        if let Some(before_line) = &source.before_line {
+            new.before_line = before_line.to_string();
+        }
+        if let Some(after_line) = &source.after_line {
+            new.after_line = after_line.to_string();
+        }
+        if let Some(enable) = source.enable {
+            new.enable = enable;
+        }
+        if let Some(skip_shebang_line) = source.skip_shebang_line {
+            new.skip_shebang_line = skip_shebang_line;
+        }
+        if let Some(shebang) = &source.shebang {
+            new.shebang = shebang.clone();
+        }
+        if let Some(preamble) = &source.preamble {
+            new.preamble = preamble.clone();
+        }
+        if let Some(placement) = source.placement {
+            new.placement = placement;
+        }
+        if let Some(preserve_mtime) = source.preserve_mtime {
+            new.preserve_mtime = preserve_mtime;
+        }
+        if let Some(mode) = source.mode {
+            new.mode = mode;
+        }
+        if let Some(notebook_cell) = source.notebook_cell {
+            new.notebook_cell = notebook_cell;
+        }
+
+        new
+    }
+}
+
+/// Where the header goes relative to a `---` front matter block at the top of
+/// the file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    /// At the top of the file, before any front matter
+    #[default]
+    Top,
+    /// Just after the closing `---` of the front matter
+    AfterFrontMatter,
+    /// Just after the opening `---` of the front matter, which is added if
+    /// the file has none
+    InFrontMatter,
+}
+
+struct FileTypeGlob {
+    pattern: String,
+    matcher: GlobMatcher,
+    /// Index into `FileTypeMatcher::types`
+    index: usize,
+}
+
+/// File types by exact file name and by extension, lowercase if matching
+/// ignores case.
+#[derive(Default)]
+struct FileNames {
+    /// Indexes into `FileTypeMatcher::types` by exact file name
+    file_names: HashMap<String, usize>,
+    /// Indexes into `FileTypeMatcher::types` by extension, without the `.`
+    extensions: HashMap<String, usize>,
+}
+
+impl FileNames {
+    /// Finds the index of a file's type. An exact file name wins over an
+    /// extension, and a longer extension over a shorter one. Only the text
+    /// after a `.` in the name is taken as an extension, so a script named
+    /// `go` isn't taken for a `.go` file.
+    fn get(&self, name: &str) -> Option<usize> {
+        if let Some(&index) = self.file_names.get(name) {
+            return Some(index);
+        }
+        name.match_indices('.')
+            .find_map(|(i, _)| self.extensions.get(&name[i + 1..]))
+            .copied()
+    }
+}
+
+/// Finds the file type configuration of a file from the language database and
+/// the keys of `file_types`. A key can be an exact file name such as
+/// `Dockerfile`, an extension such as `rs` or `d.ts`, or a glob such as
+/// `*.config.js`.
+pub struct FileTypeMatcher {
+    /// Names of the languages and `file_types` keys, with their configuration
+    types: Vec<(String, FileTypeConfig)>,
+    /// The `file_types` keys that aren't globs, each both a file name and an
+    /// extension
+    file_types: FileNames,
+    /// The `file_types` globs, most specific first
+    globs: Vec<FileTypeGlob>,
+    /// The file names and extensions of the languages
+    languages: FileNames,
+    /// Indexes into `types` by interpreter name, for scripts with no extension
+    interpreters: HashMap<String, usize>,
+    case_insensitive: bool,
+}
+
+impl FileTypeMatcher {
+    /// Gets the name and configuration of a file's type. The `file_types` keys
+    /// come first: an exact file name, then the longest extension, then a
+    /// glob. Globs with a `/` are matched against the path from the project
+    /// root, and other globs against the file name. The languages' file
+    /// names and extensions are tried last.
+    pub fn get(&self, file_name: &str, relative_path: &str) -> Option<(&str, &FileTypeConfig)> {
+        let name = self.key(file_name);
+
+        let index = self
+            .file_types
+            .get(&name)
+            .or_else(|| {
+                self.globs
+                    .iter()
+                    .find(|glob| {
+                        if glob.pattern.contains('/') {
+                            glob.matcher.is_match(relative_path)
+                        } else {
+                            glob.matcher.is_match(file_name)
+                        }
+                    })
+                    .map(|glob| glob.index)
+            })
+            .or_else(|| self.languages.get(&name))?;
+
+        self.get_index(index)
+    }
+
+    /// Gets the name and configuration of a script's type from the interpreter
+    /// in its shebang line, such as `python3`. A version at the end of the
+    /// name is ignored if only the plain name is known.
+    pub fn get_by_interpreter(&self, interpreter: &str) -> Option<(&str, &FileTypeConfig)> {
+        let index = self.interpreters.get(interpreter).or_else(|| {
+            let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+            self.interpreters.get(name)
+        })?;
+
+        self.get_index(*index)
+    }
+
+    fn get_index(&self, index: usize) -> Option<(&str, &FileTypeConfig)> {
+        let (name, config) = self.types.get(index)?;
+        Some((name, config))
+    }
+
+    pub fn configs(&self) -> impl Iterator<Item = &FileTypeConfig> {
+        self.types.iter().map(|(_, config)| config)
+    }
+
+    /// Gets the key a file name or extension is stored under.
+    fn key(&self, name: &str) -> String {
+        if self.case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Points a language's file name or extension at the language at `index`.
+    /// Two languages can't share one.
+    fn insert_language_name(
+        &mut self,
+        name: &str,
+        index: usize,
+        is_extension: bool,
+    ) -> Result<(), FileTypeErr> {
+        let name = self.key(name);
+        let names = if is_extension {
+            &mut self.languages.extensions
+        } else {
+            &mut self.languages.file_names
+        };
+
+        if let Some(&existing) = names.get(&name) {
+            if existing != index {
+                return Err(FileTypeErr::SharedName {
+                    name,
+                    languages: (self.types[existing].0.clone(), self.types[index].0.clone()),
+                });
+            }
+        }
+        names.insert(name, index);
+
+        Ok(())
+    }
+}
+
+/// A problem with the file types of a config.
+#[derive(Debug)]
+pub enum FileTypeErr {
+    InvalidGlob(globset::Error),
+    /// A language has no comments to write the header in
+    NoComments(String),
+    /// Two languages have the same extension, file name or interpreter
+    SharedName {
+        name: String,
+        languages: (String, String),
+    },
+    /// An interpreter names a file type that doesn't exist
+    UnknownFileType {
+        interpreter: String,
+        file_type: String,
+    },
+}
+
+impl fmt::Display for FileTypeErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileTypeErr::InvalidGlob(e) => write!(f, "Invalid glob in \"file_types\": {}", e),
+            FileTypeErr::NoComments(language) => write!(
+                f,
+                "The language \"{}\" needs a \"line_comment\" or a \"block_comment\"",
+                language
+            ),
+            FileTypeErr::SharedName {
+                name,
+                languages: (first, second),
+            } => write!(
+                f,
+                "The languages \"{}\" and \"{}\" both claim \"{}\"",
+                first, second, name
+            ),
+            FileTypeErr::UnknownFileType {
+                interpreter,
+                file_type,
+            } => write!(
+                f,
+                "The interpreter \"{}\" uses \"{}\", which isn't a file type or a language",
+                interpreter, file_type
+            ),
+        }
+    }
+}
+
+/// How the header is stored in a file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FileMode {
+    /// As a comment at the top of the file
+    #[default]
+    Inline,
+    /// As a tagged cell at the top of a Jupyter notebook
+    Notebook,
+    /// As SPDX tags in a `<file>.license` file next to it, for files that
+    /// can't hold comments
+    Sidecar,
+}
+
+/// The type of notebook cell that holds the header.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NotebookCell {
+    #[default]
+    Code,
+    Markdown,
+}
+
+/// A list of regexes, each matched against the start of a line.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct LinePatterns {
+    pub patterns: Vec<String>,
+    regexes: Vec<Regex>,
+}
+
+impl LinePatterns {
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regexes.iter().any(|regex| regex.is_match(line))
+    }
+}
+
+impl TryFrom<Vec<String>> for LinePatterns {
+    type Error = regex::Error;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| Regex::new(&format!("^(?:{})", pattern)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { patterns, regexes })
+    }
+}
+
+impl From<LinePatterns> for Vec<String> {
+    fn from(line_patterns: LinePatterns) -> Self {
+        line_patterns.patterns
+    }
+}
+
+/// A list of glob patterns, matched against paths relative to the project root.
+/// `*` doesn't match `/`, but `**` does.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "Vec<String>")]
+pub struct PathGlobs {
+    pub patterns: Vec<String>,
+    set: GlobSet,
+}
+
+impl PathGlobs {
+    pub fn is_match(&self, relative_path: &str) -> bool {
+        self.set.is_match(relative_path)
+    }
+}
+
+impl TryFrom<Vec<String>> for PathGlobs {
+    type Error = globset::Error;
+
+    fn try_from(patterns: Vec<String>) -> Result<Self, Self::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &patterns {
+            builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+        }
+
+        Ok(Self {
+            set: builder.build()?,
+            patterns,
+        })
+    }
+}
+
+/// Settings for the files matching some glob patterns.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Override {
+    pub paths: PathGlobs,
+    /// Path of a license template to use instead of `.licensesnip`, relative
+    /// to the project root
+    pub license: Option<PathBuf>,
+    pub variables: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    pub file_type: PartialFileTypeConfig,
+}
+
+/// Which year is written into `%YEAR%`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "RawYearPolicy")]
+pub enum YearPolicy {
+    /// Always use the same year.
+    Fixed(i32),
+    /// Use the current year.
+    Current,
+    /// Use the year the file was first committed to git.
+    Creation,
+    /// Use a range from the year the file was first committed to the current year.
+    Range,
+    /// Keep the year of an existing header, or use the current year if there is none.
+    Preserve,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawYearPolicy {
+    Year(i32),
+    Name(String),
+}
+
+impl TryFrom<RawYearPolicy> for YearPolicy {
+    type Error = String;
+
+    fn try_from(raw: RawYearPolicy) -> Result<Self, Self::Error> {
+        match raw {
+            RawYearPolicy::Year(year) => Ok(YearPolicy::Fixed(year)),
+            RawYearPolicy::Name(name) => match name.as_str() {
+                "current" => Ok(YearPolicy::Current),
+                "creation" => Ok(YearPolicy::Creation),
+                "range" | "first-last" => Ok(YearPolicy::Range),
+                "preserve" => Ok(YearPolicy::Preserve),
+                _ => Err(format!(
+                    "unknown year_policy \"{}\", expected a year or one of \"current\", \"creation\", \"range\", \"preserve\"",
+                    name
+                )),
+            },
+        }
+    }
+}
+
+/// Where the text of the license header comes from.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderMode {
+    /// The `.licensesnip` file
+    Template,
+    /// `SPDX-FileCopyrightText` and `SPDX-License-Identifier` tags built from
+    /// `spdx_id` and `copyright_holder`
+    Spdx,
+}
+
+/// What to do with files that are symlinks.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    /// Leave them alone
+    Skip,
+    /// Edit the files they point to
+    Follow,
+    /// Stop with an error
+    Error,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub use_gitignore: bool,
+    pub parallel: bool,
+    pub cache: bool,
+    /// Whether `file_types` keys match file names whatever their case
+    pub case_insensitive: bool,
+    pub header_mode: HeaderMode,
+    pub spdx_id: Option<String>,
+    pub copyright_holder: Option<String>,
+    pub year_policy: YearPolicy,
+    pub symlinks: SymlinkPolicy,
+    pub variables: HashMap<String, String>,
+    pub overrides: Vec<Override>,
+    /// File type keys or language names by interpreter name, e.g. `python3`
+    /// to `py`
+    pub interpreters: HashMap<String, String>,
+    pub languages: HashMap<String, Language>,
     pub file_types: HashMap<String, FileTypeConfig>,
 }
 
 impl Config {
-    pub fn get_filetype_map(&self) -> HashMap<String, FileTypeConfig> {
-        let mut map = HashMap::<String, FileTypeConfig>::new();
+    /// Builds the matcher for the languages and `file_types` of this config.
+    /// Languages are checked for comments and for names they share with other
+    /// languages, and `interpreters` for the file types they name.
+    pub fn get_filetype_matcher(&self) -> Result<FileTypeMatcher, FileTypeErr> {
+        let mut matcher = FileTypeMatcher {
+            types: Vec::new(),
+            file_types: FileNames::default(),
+            globs: Vec::new(),
+            languages: FileNames::default(),
+            interpreters: HashMap::new(),
+            case_insensitive: self.case_insensitive,
+        };
+
+        let mut languages = self.languages.iter().collect::<Vec<_>>();
+        let language_count = languages.len();
+        languages.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, language) in languages {
+            let config = language.file_type_config();
+            if config.enable && config.mode != FileMode::Sidecar && !language.has_comments() {
+                return Err(FileTypeErr::NoComments(name.to_string()));
+            }
+
+            let index = matcher.types.len();
+            matcher.types.push((name.to_string(), config));
+            for extension in language.extensions.iter().flatten() {
+                matcher.insert_language_name(extension, index, true)?;
+            }
+            for file_name in language.filenames.iter().flatten() {
+                matcher.insert_language_name(file_name, index, false)?;
+            }
+            for interpreter in language.interpreters.iter().flatten() {
+                if let Some(&existing) = matcher.interpreters.get(interpreter) {
+                    return Err(FileTypeErr::SharedName {
+                        name: interpreter.to_string(),
+                        languages: (matcher.types[existing].0.clone(), name.to_string()),
+                    });
+                }
+                matcher.interpreters.insert(interpreter.to_string(), index);
+            }
+        }
+
         for (types, config) in &self.file_types {
-            let split = types.split(',');
-            for extension in split {
-                map.insert(extension.to_string(), config.clone());
+            for file_type in types.split(',') {
+                let index = matcher.types.len();
+                matcher.types.push((file_type.to_string(), config.clone()));
+                if file_type.contains(['*', '?', '[', '{']) {
+                    let glob = GlobBuilder::new(file_type)
+                        .literal_separator(true)
+                        .case_insensitive(self.case_insensitive)
+                        .build()
+                        .map_err(FileTypeErr::InvalidGlob)?;
+                    matcher.globs.push(FileTypeGlob {
+                        pattern: file_type.to_string(),
+                        matcher: glob.compile_matcher(),
+                        index,
+                    });
+                } else {
+                    let key = matcher.key(file_type);
+                    matcher.file_types.file_names.insert(key.clone(), index);
+                    matcher.file_types.extensions.insert(key, index);
+                }
             }
         }
 
-        map
+        // The longest pattern is taken to be the most specific
+        matcher.globs.sort_by(|a, b| {
+            b.pattern
+                .len()
+                .cmp(&a.pattern.len())
+                .then_with(|| a.pattern.cmp(&b.pattern))
+        });
+
+        for (interpreter, file_type) in &self.interpreters {
+            let key = matcher.key(file_type);
+            let index = [&matcher.file_types, &matcher.languages]
+                .iter()
+                .find_map(|names| {
+                    names
+                        .file_names
+                        .get(&key)
+                        .or_else(|| names.extensions.get(&key))
+                })
+                .copied()
+                .or_else(|| {
+                    matcher.types[..language_count]
+                        .iter()
+                        .position(|(name, _)| name == file_type)
+                });
+            match index {
+                Some(index) => {
+                    matcher.interpreters.insert(interpreter.to_string(), index);
+                }
+                None => {
+                    return Err(FileTypeErr::UnknownFileType {
+                        interpreter: interpreter.to_string(),
+                        file_type: file_type.to_string(),
+                    })
+                }
+            }
+        }
+
+        Ok(matcher)
     }
 
     pub fn assign_partial(target: &Self, source: &PartialConfig) -> Self {
@@ -76,6 +651,65 @@ impl Config {
             new.use_gitignore = use_gitignore;
         }
 
+        if let Some(parallel) = source.parallel {
+            new.parallel = parallel;
+        }
+
+        if let Some(cache) = source.cache {
+            new.cache = cache;
+        }
+
+        if let Some(case_insensitive) = source.case_insensitive {
+            new.case_insensitive = case_insensitive;
+        }
+
+        if let Some(header_mode) = source.header_mode {
+            new.header_mode = header_mode;
+        }
+
+        if let Some(spdx_id) = &source.spdx_id {
+            new.spdx_id = Some(spdx_id.to_string());
+        }
+
+        if let Some(copyright_holder) = &source.copyright_holder {
+            new.copyright_holder = Some(copyright_holder.to_string());
+        }
+
+        if let Some(year_policy) = source.year_policy {
+            new.year_policy = year_policy;
+        }
+
+        if let Some(symlinks) = source.symlinks {
+            new.symlinks = symlinks;
+        }
+
+        if let Some(variables) = &source.variables {
+            for (name, value) in variables {
+                new.variables.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        if let Some(overrides) = &source.overrides {
+            new.overrides.extend(overrides.iter().cloned());
+        }
+
+        if let Some(interpreters) = &source.interpreters {
+            for (interpreter, file_type) in interpreters {
+                new.interpreters
+                    .insert(interpreter.to_string(), file_type.to_string());
+            }
+        }
+
+        if let Some(languages) = &source.languages {
+            for (name, language) in languages {
+                let merged = match new.languages.get(name) {
+                    Some(existing) => Language::assign(existing, language),
+                    None => language.clone(),
+                };
+                new.languages.insert(name.to_string(), merged);
+            }
+        }
+
         if let Some(file_types) = &source.file_types {
             for (filetypes, cfg) in file_types {
                 new.file_types.insert(filetypes.to_string(), cfg.clone());
@@ -90,6 +724,18 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             use_gitignore: true,
+            parallel: true,
+            cache: false,
+            case_insensitive: false,
+            header_mode: HeaderMode::Template,
+            spdx_id: None,
+            copyright_holder: None,
+            year_policy: YearPolicy::Current,
+            symlinks: SymlinkPolicy::Skip,
+            variables: HashMap::<String, String>::new(),
+            overrides: Vec::<Override>::new(),
+            interpreters: HashMap::<String, String>::new(),
+            languages: HashMap::<String, Language>::new(),
             file_types: HashMap::<String, FileTypeConfig>::new(),
         }
     }
@@ -98,15 +744,35 @@ impl Default for Config {
 #[derive(Deserialize, Debug, Clone)]
 pub struct PartialConfig {
     pub use_gitignore: Option<bool>,
+    pub parallel: Option<bool>,
+    pub cache: Option<bool>,
+    pub case_insensitive: Option<bool>,
+    pub header_mode: Option<HeaderMode>,
+    pub spdx_id: Option<String>,
+    pub copyright_holder: Option<String>,
+    pub year_policy: Option<YearPolicy>,
+    pub symlinks: Option<SymlinkPolicy>,
+    pub variables: Option<HashMap<String, String>>,
+    pub overrides: Option<Vec<Override>>,
+    pub interpreters: Option<HashMap<String, String>>,
+    pub languages: Option<HashMap<String, Language>>,
     pub file_types: Option<HashMap<String, FileTypeConfig>>,
 }
 
 impl PartialConfig {
+    /// Loads the base config, with the languages compiled into the binary.
     pub fn base() -> Result<Self, LoadConfigErr> {
-        match serde_json::from_str(BASE_CONFIG) {
-            Ok(config) => Ok(config),
-            Err(e) => Err(LoadConfigErr::JsonFormattingErr(e)),
+        let mut config: Self = match serde_json::from_str(BASE_CONFIG) {
+            Ok(config) => config,
+            Err(e) => return Err(LoadConfigErr::JsonFormattingErr(e)),
+        };
+
+        match builtin_languages() {
+            Ok(languages) => config.languages = Some(languages),
+            Err(e) => return Err(LoadConfigErr::JsonFormattingErr(e)),
         }
+
+        Ok(config)
     }
 
     pub fn from_path(path: &Path, create_default: bool) -> Result<Self, LoadConfigErr> {
@@ -144,6 +810,72 @@ impl PartialConfig {
             new.use_gitignore = Some(use_gitignore);
         }
 
+        if let Some(parallel) = source.parallel {
+            new.parallel = Some(parallel);
+        }
+
+        if let Some(cache) = source.cache {
+            new.cache = Some(cache);
+        }
+
+        if let Some(case_insensitive) = source.case_insensitive {
+            new.case_insensitive = Some(case_insensitive);
+        }
+
+        if let Some(header_mode) = source.header_mode {
+            new.header_mode = Some(header_mode);
+        }
+
+        if let Some(spdx_id) = &source.spdx_id {
+            new.spdx_id = Some(spdx_id.to_string());
+        }
+
+        if let Some(copyright_holder) = &source.copyright_holder {
+            new.copyright_holder = Some(copyright_holder.to_string());
+        }
+
+        if let Some(year_policy) = source.year_policy {
+            new.year_policy = Some(year_policy);
+        }
+
+        if let Some(symlinks) = source.symlinks {
+            new.symlinks = Some(symlinks);
+        }
+
+        if let Some(variables) = &source.variables {
+            let mut merged = new.variables.unwrap_or_default();
+            for (name, value) in variables {
+                merged.insert(name.to_string(), value.to_string());
+            }
+            new.variables = Some(merged);
+        }
+
+        if let Some(overrides) = &source.overrides {
+            let mut merged = new.overrides.unwrap_or_default();
+            merged.extend(overrides.iter().cloned());
+            new.overrides = Some(merged);
+        }
+
+        if let Some(interpreters) = &source.interpreters {
+            let mut merged = new.interpreters.unwrap_or_default();
+            for (interpreter, file_type) in interpreters {
+                merged.insert(interpreter.to_string(), file_type.to_string());
+            }
+            new.interpreters = Some(merged);
+        }
+
+        if let Some(languages) = &source.languages {
+            let mut merged = new.languages.unwrap_or_default();
+            for (name, language) in languages {
+                let language = match merged.get(name) {
+                    Some(existing) => Language::assign(existing, language),
+                    None => language.clone(),
+                };
+                merged.insert(name.to_string(), language);
+            }
+            new.languages = Some(merged);
+        }
+
         if let Some(file_types) = &source.file_types {
             for (filetypes, cfg) in file_types {
                 if let Some(f) = &mut new.file_types {
@@ -160,6 +892,12 @@ fn get_true() -> bool {
     true
 }
 
+/// Matches `#!/usr/bin/env sh` and `#! /bin/sh`, but not Rust's `#![...]`.
+fn default_shebang() -> LinePatterns {
+    LinePatterns::try_from(vec![String::from("#! ?/")])
+        .expect("shebang pattern should be a valid regex")
+}
+
 pub enum LoadConfigErr {
     JsonFormattingErr(serde_json::Error),
     CreateDefaultConfigErr,
@@ -167,7 +905,9 @@ pub enum LoadConfigErr {
     NotFoundErr,
 }
 
-pub fn load_config() -> Result<Config, LoadConfigErr> {
+/// Loads the config of the project at `root`, on top of the user config and the
+/// base config.
+pub fn load_config(root: &Path) -> Result<Config, LoadConfigErr> {
     let config_path = match user_config_path() {
         Ok(d) => d,
         Err(_) => return Err(LoadConfigErr::LoadUserConfigErr),
@@ -175,7 +915,7 @@ pub fn load_config() -> Result<Config, LoadConfigErr> {
 
     let user_config = PartialConfig::from_path(&config_path, true)?;
 
-    let cwd_config = match PartialConfig::from_path(Path::new(CFG_PATH), false) {
+    let project_config = match PartialConfig::from_path(&root.join(CFG_PATH), false) {
         Ok(c) => Some(c),
         Err(e) => match e {
             LoadConfigErr::JsonFormattingErr(e) => return Err(LoadConfigErr::JsonFormattingErr(e)),
@@ -190,13 +930,44 @@ pub fn load_config() -> Result<Config, LoadConfigErr> {
 
     let mut assigned = user_config.clone();
 
-    if let Some(c) = cwd_config {
+    if let Some(c) = project_config {
         assigned = PartialConfig::assign(&user_config, &c);
     }
 
     Ok(Config::assign_partial(&base, &assigned))
 }
 
+/// Finds the root of the project that `path` belongs to by walking up from it.
+///
+/// The root is the nearest directory with a config file or a `.git`, or
+/// failing that the nearest directory with a `.licensesnip`. If there is
+/// neither, it is the current working directory.
+pub fn find_project_root(path: &Path) -> PathBuf {
+    let cwd = std::env::current_dir()
+        .and_then(fs::canonicalize)
+        .unwrap_or_default();
+    let start = match fs::canonicalize(path) {
+        Ok(p) if p.is_dir() => p,
+        Ok(p) => match p.parent() {
+            Some(parent) => parent.to_path_buf(),
+            None => return cwd,
+        },
+        Err(_) => return cwd,
+    };
+
+    let mut license_dir = None;
+    for dir in start.ancestors() {
+        if dir.join(CFG_PATH).is_file() || dir.join(".git").exists() {
+            return dir.to_path_buf();
+        }
+        if license_dir.is_none() && dir.join(LICENSE_PATH).is_file() {
+            license_dir = Some(dir.to_path_buf());
+        }
+    }
+
+    license_dir.unwrap_or(cwd)
+}
+
 #[derive(Debug)]
 enum CreateDefaultConfigErr {
     MissingPathParentErr,
@@ -245,3 +1016,168 @@ pub fn user_config_path() -> Result<PathBuf, NoConfigDirErr> {
 
     Ok(config_dir.join(CFG_PATH))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtin_config() -> Config {
+        let Ok(base) = PartialConfig::base() else {
+            panic!("base config should load");
+        };
+        Config::assign_partial(&Config::default(), &base)
+    }
+
+    fn builtin_matcher() -> FileTypeMatcher {
+        builtin_config().get_filetype_matcher().unwrap()
+    }
+
+    #[test]
+    fn matches_files_to_their_language() {
+        let matcher = builtin_matcher();
+        for (path, language, before_line) in [
+            ("src/x.h", "C", "// "),
+            ("src/x.hpp", "C++", "// "),
+            ("Dockerfile", "Dockerfile", "# "),
+            ("CMakeLists.txt", "CMake", "# "),
+            ("src/x.d.ts", "TypeScript", "// "),
+            ("build.bat", "Batch", "REM "),
+            ("infra/main.tf", "HCL", "# "),
+        ] {
+            let file_name = path.rsplit('/').next().unwrap();
+            let Some((name, config)) = matcher.get(file_name, path) else {
+                panic!("{} matched no file type", path);
+            };
+            assert_eq!(name, language, "{}", path);
+            assert_eq!(config.before_line, before_line, "{}", path);
+            assert_eq!(config.before_block, "", "{}", path);
+        }
+    }
+
+    #[test]
+    fn uses_block_comments_without_line_comments() {
+        let matcher = builtin_matcher();
+        let (name, config) = matcher.get("App.svelte", "src/App.svelte").unwrap();
+        assert_eq!(name, "Svelte");
+        assert_eq!(config.before_block, "<!--");
+        assert_eq!(config.after_block, "-->");
+    }
+
+    #[test]
+    fn keeps_the_file_types_of_the_old_base_config() {
+        let matcher = builtin_matcher();
+        for (extensions, before_block, before_line) in [
+            ("js,mjs,ts,cjs,jsx,tsx", "", "// "),
+            ("vue,html", "<!--", "  "),
+            ("gitignore,npmignore,eslintignore,dockerignore,sh", "", "# "),
+            ("rs", "", "// "),
+            ("css,scss,less", "/*", "  "),
+            ("py", "", "# "),
+            ("c,cpp,php,as,java,go,cto,acl", "", "// "),
+        ] {
+            for extension in extensions.split(',') {
+                let file_name = format!("x.{}", extension);
+                let Some((_, config)) = matcher.get(&file_name, &file_name) else {
+                    panic!("{} matched no file type", file_name);
+                };
+                assert_eq!(config.before_block, before_block, "{}", file_name);
+                assert_eq!(config.before_line, before_line, "{}", file_name);
+            }
+        }
+        assert!(matcher
+            .get(".licensesnipignore", ".licensesnipignore")
+            .is_none());
+    }
+
+    #[test]
+    fn takes_extensions_only_after_a_dot() {
+        let matcher = builtin_matcher();
+        assert_eq!(matcher.get("main.go", "cmd/main.go").unwrap().0, "Go");
+        for name in ["go", "m", "mm"] {
+            let file_path = format!("bin/{}", name);
+            assert!(matcher.get(name, &file_path).is_none(), "{}", file_path);
+        }
+    }
+
+    #[test]
+    fn matches_interpreters_to_their_language() {
+        let matcher = builtin_matcher();
+        let language = |interpreter| {
+            matcher
+                .get_by_interpreter(interpreter)
+                .map(|(name, _)| name)
+        };
+        assert_eq!(language("bash"), Some("Shell"));
+        assert_eq!(language("sh"), Some("Shell"));
+        assert_eq!(language("python"), Some("Python"));
+        // Versions are ignored when only the plain name is known
+        assert_eq!(language("python3"), Some("Python"));
+        assert_eq!(language("python3.12"), Some("Python"));
+        assert_eq!(language("no-such-shell"), None);
+    }
+
+    #[test]
+    fn matches_interpreters_from_the_config() {
+        let mut config = builtin_config();
+        config.file_types.insert(
+            String::from("tsx"),
+            FileTypeConfig {
+                before_line: String::from("/// "),
+                ..FileTypeConfig::default()
+            },
+        );
+        for (interpreter, file_type) in [("deno", "tsx"), ("python3", "Ruby"), ("jq", "sh")] {
+            config
+                .interpreters
+                .insert(interpreter.to_string(), file_type.to_string());
+        }
+        let matcher = config.get_filetype_matcher().unwrap();
+
+        let (name, deno) = matcher.get_by_interpreter("deno").unwrap();
+        assert_eq!((name, deno.before_line.as_str()), ("tsx", "/// "));
+        // An exact name wins over the name without its version
+        assert_eq!(matcher.get_by_interpreter("python3").unwrap().0, "Ruby");
+        assert_eq!(matcher.get_by_interpreter("python").unwrap().0, "Python");
+        assert_eq!(matcher.get_by_interpreter("jq").unwrap().0, "Shell");
+
+        config
+            .interpreters
+            .insert(String::from("node"), String::from("no-such-type"));
+        assert!(matches!(
+            config.get_filetype_matcher(),
+            Err(FileTypeErr::UnknownFileType { .. })
+        ));
+    }
+
+    #[test]
+    fn prefers_file_types_globs_to_language_extensions() {
+        let mut config = builtin_config();
+        let block = FileTypeConfig {
+            before_block: String::from("/*"),
+            ..FileTypeConfig::default()
+        };
+        config
+            .file_types
+            .insert(String::from("*.config.js,scripts/*.sh"), block);
+        let matcher = config.get_filetype_matcher().unwrap();
+
+        for (file_name, path) in [
+            ("webpack.config.js", "webpack.config.js"),
+            ("build.sh", "scripts/build.sh"),
+        ] {
+            let (name, config) = matcher.get(file_name, path).unwrap();
+            assert_ne!(name, "JavaScript");
+            assert_eq!(config.before_block, "/*", "{}", path);
+        }
+        assert_eq!(matcher.get("app.js", "app.js").unwrap().0, "JavaScript");
+        assert_eq!(matcher.get("build.sh", "build.sh").unwrap().0, "Shell");
+    }
+
+    #[test]
+    fn leaves_unknown_files_unmatched() {
+        let matcher = builtin_matcher();
+        assert!(matcher
+            .get("notes.unknown-ext", "notes.unknown-ext")
+            .is_none());
+    }
+}
@@ -22,18 +22,39 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
 
+use chrono::{DateTime, Utc};
 use colored::Colorize;
-use ignore::{Walk, WalkBuilder};
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use regex::bytes::Regex;
 
 use crate::{
-    config::{load_config, Config, FileTypeConfig, LoadConfigErr},
-    license::{read_license, License, ReadLicenseErr},
+    cache::{Cache, CACHE_PATH},
+    config::{
+        load_config, Config, FileMode, FileTypeConfig, FileTypeMatcher, HeaderMode, LoadConfigErr,
+        NotebookCell, Override, SymlinkPolicy, YearPolicy,
+    },
+    git::{FileHistory, GitHistory},
+    license::{
+        header_file_path, read_header_year, read_license, spdx_license, License, ReadLicenseErr,
+        TemplateVars, LICENSE_PATH, SIDECAR_SUFFIX,
+    },
+    template::TemplateErr,
+    year::{current_date, current_year, now, policy_year},
 };
 
-pub fn f_load_config() -> Config {
-    match load_config() {
+pub fn f_load_config(root: &Path) -> Config {
+    match load_config(root) {
         Ok(cfg) => cfg,
         Err(e) => match e {
             LoadConfigErr::JsonFormattingErr(e) => {
@@ -56,138 +77,1187 @@ pub fn f_load_config() -> Config {
     }
 }
 
-pub fn f_read_license() -> License {
-    match read_license() {
+pub fn f_read_license(config: &Config, root: &Path) -> License {
+    let result = match config.header_mode {
+        HeaderMode::Template => read_license(root),
+        HeaderMode::Spdx => spdx_license(config),
+    };
+
+    match result {
         Ok(l) => l,
-        Err(e) => match e {
-            ReadLicenseErr::FileReadErr => {
-                println!("{}", "Error: Couldn't find a .licensesnip file in the current working directory's root.".red());
-                std::process::exit(exitcode::CONFIG)
-            }
-        },
+        Err(e) => {
+            let message = match e {
+                ReadLicenseErr::FileReadErr => format!(
+                    "Error: Couldn't find a .licensesnip file in the project root {}. Run `licensesnip init --license <id>` to create one.",
+                    root.display()
+                ),
+                ReadLicenseErr::MissingSpdxId => String::from(
+                    "Error: \"header_mode\": \"spdx\" needs an \"spdx_id\" in your config.",
+                ),
+                ReadLicenseErr::MissingCopyrightHolder => String::from(
+                    "Error: \"header_mode\": \"spdx\" needs a \"copyright_holder\" in your config.",
+                ),
+                ReadLicenseErr::InvalidSpdxExpression(expression, e) => format!(
+                    "Error: \"{}\" isn't a valid SPDX license expression: {}",
+                    expression, e
+                ),
+            };
+            println!("{}", message.red());
+            std::process::exit(exitcode::CONFIG)
+        }
+    }
+}
+
+/// Builds the SPDX tags that go in sidecar files and `REUSE.toml`, exiting
+/// with an error if the config doesn't say what they are.
+pub fn f_sidecar_license(config: &Config) -> License {
+    match spdx_license(config) {
+        Ok(license) => {
+            f_check_license_variables(&license, &config.variables, "copyright_holder");
+            license
+        }
+        Err(e) => {
+            let message = match e {
+                ReadLicenseErr::MissingSpdxId => String::from(
+                    "Error: Sidecar files and REUSE.toml annotations need an \"spdx_id\" in your config.",
+                ),
+                ReadLicenseErr::MissingCopyrightHolder => String::from(
+                    "Error: Sidecar files and REUSE.toml annotations need a \"copyright_holder\" in your config.",
+                ),
+                ReadLicenseErr::InvalidSpdxExpression(expression, e) => format!(
+                    "Error: \"{}\" isn't a valid SPDX license expression: {}",
+                    expression, e
+                ),
+                ReadLicenseErr::FileReadErr => unreachable!("SPDX tags aren't read from a file"),
+            };
+            println!("{}", message.red());
+            std::process::exit(exitcode::CONFIG)
+        }
+    }
+}
+
+/// Loads the project's cache if it is enabled in the config.
+pub fn f_load_cache(config: &Config, root: &Path, license: &License) -> Option<Cache> {
+    config.cache.then(|| Cache::load(root, license))
+}
+
+pub fn f_save_cache(cache: Option<Cache>) {
+    if let Some(Err(e)) = cache.map(Cache::save) {
+        let warning = format!("⚠ Couldn't save {}: {}", CACHE_PATH, e);
+        println!("{}", warning.yellow());
+    }
+}
+
+pub fn f_current_year(year_override: Option<i32>) -> i32 {
+    match current_year(year_override) {
+        Ok(year) => year,
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).red());
+            std::process::exit(exitcode::CONFIG)
+        }
+    }
+}
+
+pub fn f_now() -> DateTime<Utc> {
+    match now() {
+        Ok(time) => time,
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).red());
+            std::process::exit(exitcode::CONFIG)
+        }
+    }
+}
+
+pub fn f_current_date() -> String {
+    match current_date() {
+        Ok(date) => date,
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).red());
+            std::process::exit(exitcode::CONFIG)
+        }
     }
 }
 
 pub struct FileWalk {
-    ignore_walk: Walk,
+    path: PathBuf,
+    use_gitignore: bool,
+    /// Number of threads to walk with, where 0 picks one per CPU
+    threads: usize,
     verbose: bool,
-    filetype_map: HashMap<String, FileTypeConfig>,
-    pub matched_filetypes_count: u32,
-    license: License,
+    file_types: FileTypeMatcher,
+    matched_filetypes_count: AtomicU32,
+    license: Arc<License>,
+    /// Whether files use the nearest `.licensesnip` in their ancestors
+    hierarchical: bool,
+    /// License that applies to each directory that has been looked up
+    licenses: Mutex<HashMap<PathBuf, Arc<License>>>,
+    /// SPDX tags for files in sidecar mode, if any file type uses it
+    sidecar_license: Option<Arc<License>>,
     year: i32,
+    year_policy: YearPolicy,
+    symlinks: SymlinkPolicy,
+    date: String,
+    variables: HashMap<String, String>,
+    overrides: Vec<Override>,
+    /// Licenses of overrides, keyed by template path
+    override_licenses: Mutex<HashMap<PathBuf, Arc<License>>>,
+    root: PathBuf,
+    header_patterns: Mutex<HashMap<String, Regex>>,
+    git_history: Mutex<GitHistory>,
+    uses_git_history: bool,
 }
 
 impl FileWalk {
-    pub fn new(path: PathBuf, config: Config, license: License, year: i32, verbose: bool) -> Self {
-        let filetype_map = config.get_filetype_map();
-        let mut builder = WalkBuilder::new(path);
-        let walk = builder
-            .git_ignore(config.use_gitignore)
-            .add_custom_ignore_filename(".licensesnipignore");
-        // walk.add_ignore(Path::new("./.licensesnipignore"));
-        let ignore_walk = walk.build();
+    pub fn new(
+        path: PathBuf,
+        root: PathBuf,
+        config: Config,
+        license: License,
+        year: i32,
+        verbose: bool,
+        jobs: Option<usize>,
+    ) -> Self {
+        let license_source = match config.header_mode {
+            HeaderMode::Template => LICENSE_PATH,
+            HeaderMode::Spdx => "copyright_holder",
+        };
+        f_check_license_variables(&license, &config.variables, license_source);
+
+        let file_types = match config.get_filetype_matcher() {
+            Ok(file_types) => file_types,
+            Err(e) => {
+                let error = format!("Error: {}", e);
+                println!("{}", error.red());
+                std::process::exit(exitcode::CONFIG);
+            }
+        };
+        let uses_sidecars = file_types.configs().any(|c| c.mode == FileMode::Sidecar)
+            || config
+                .overrides
+                .iter()
+                .any(|o| o.file_type.mode == Some(FileMode::Sidecar));
+        let sidecar_license = uses_sidecars.then(|| Arc::new(f_sidecar_license(&config)));
+        let year_policy = config.year_policy;
+        let uses_git_history = matches!(year_policy, YearPolicy::Creation | YearPolicy::Range);
+        let hierarchical = config.header_mode == HeaderMode::Template;
+        let threads = if config.parallel {
+            jobs.unwrap_or(0)
+        } else {
+            1
+        };
 
         Self {
-            ignore_walk,
+            path,
+            use_gitignore: config.use_gitignore,
+            threads,
             verbose,
-            filetype_map,
-            license,
+            file_types,
+            matched_filetypes_count: AtomicU32::new(0),
+            license: Arc::new(license),
+            hierarchical,
+            licenses: Mutex::new(HashMap::new()),
+            sidecar_license,
+            year,
+            year_policy,
+            symlinks: config.symlinks,
+            date: f_current_date(),
+            variables: config.variables,
+            overrides: config.overrides,
+            override_licenses: Mutex::new(HashMap::new()),
+            root,
+            header_patterns: Mutex::new(HashMap::new()),
+            git_history: Mutex::new(GitHistory::new()),
+            uses_git_history,
+        }
+    }
+
+    /// Number of files found that have a file type configuration.
+    pub fn matched_filetypes_count(&self) -> u32 {
+        self.matched_filetypes_count.load(Ordering::Relaxed)
+    }
+
+    /// Runs `process` on each file of the walk, on several threads unless
+    /// `parallel` is off in the config. Reports come back in path order, so
+    /// that output is the same from run to run.
+    pub fn run<R, F>(&self, process: F) -> Vec<FileReport<R>>
+    where
+        R: Send,
+        F: Fn(FileData) -> R + Sync,
+    {
+        let mut builder = WalkBuilder::new(&self.path);
+        builder
+            .git_ignore(self.use_gitignore)
+            .add_custom_ignore_filename(".licensesnipignore")
+            .threads(self.threads);
+
+        let visited = Visited::default();
+        let mut reports: Vec<FileReport<R>> = if self.threads == 1 {
+            builder
+                .build()
+                .filter_map(|entry| self.visit(entry, &process, &visited))
+                .collect()
+        } else {
+            let reports = Mutex::new(Vec::new());
+            builder.build_parallel().run(|| {
+                Box::new(|entry| {
+                    if let Some(report) = self.visit(entry, &process, &visited) {
+                        reports.lock().unwrap().push(report);
+                    }
+                    WalkState::Continue
+                })
+            });
+            reports.into_inner().unwrap()
+        };
+
+        // Symlinks come after every other file, so that a file is edited
+        // through its own path rather than a link to it
+        let mut links = visited.links.into_inner().unwrap();
+        links.sort_by(|a, b| a.path().cmp(b.path()));
+        for link in links {
+            let target = fs::canonicalize(link.path()).unwrap_or_else(|_| link.path().into());
+            if visited.files.lock().unwrap().insert(target.clone()) {
+                reports.extend(self.visit_file(link, &process));
+            } else if self.verbose {
+                reports.push(FileReport {
+                    outcome: FileOutcome::Skipped(format!(
+                        "(skipped) Symlink to a file that was already processed, {} - {}",
+                        target.display(),
+                        link.path().display()
+                    )),
+                    path: link.into_path(),
+                });
+            }
+        }
+
+        reports.sort_by(|a, b| a.path.cmp(&b.path));
+        reports
+    }
+
+    /// Lists every file in the walk in path order, including the ones that
+    /// `.licensesnipignore` leaves out and the ones with no file type
+    /// configuration. Symlinks are left out.
+    pub fn all_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = WalkBuilder::new(&self.path)
+            .git_ignore(self.use_gitignore)
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(DirEntry::into_path)
+            .collect();
+
+        files.sort();
+        files
+    }
+}
+
+impl FileWalk {
+    fn get_file_data(
+        &self,
+        entry: DirEntry,
+        file_path: String,
+        file_type_config: FileTypeConfig,
+        file_overrides: FileOverrides,
+    ) -> Result<FileData, TemplateErr> {
+        let license = match (&self.sidecar_license, &file_overrides.license) {
+            (Some(license), _) if file_type_config.mode == FileMode::Sidecar => license.clone(),
+            (_, Some(license_path)) => self.get_override_license(license_path),
+            (_, None) => self.get_license(entry.path()),
+        };
+        let variables = match &file_overrides.variables {
+            Some(v) => v,
+            None => &self.variables,
+        };
+        if file_overrides.license.is_some() || file_overrides.variables.is_some() {
+            f_check_license_variables(&license, variables, &file_path);
+        }
+
+        let pattern = license.get_header_pattern(&file_type_config, variables)?;
+        let header_pattern = self
+            .header_patterns
+            .lock()
+            .unwrap()
+            .entry(pattern)
+            .or_insert_with_key(|pattern| {
+                Regex::new(pattern).expect("escaped header pattern should be a valid regex")
+            })
+            .clone();
+
+        let uses_git_history = self.uses_git_history || license.uses_git_history();
+        let history = if uses_git_history {
+            self.git_history
+                .lock()
+                .unwrap()
+                .file_history(entry.path())
+                .cloned()
+        } else {
+            None
+        };
+        let authors = match &history {
+            Some(h) => h.authors_text(),
+            None if uses_git_history => self
+                .git_history
+                .lock()
+                .unwrap()
+                .user_name(&self.root)
+                .unwrap_or_default()
+                .to_string(),
+            None => String::new(),
+        };
+
+        let created_year = history
+            .as_ref()
+            .and_then(FileHistory::created_year)
+            .unwrap_or(self.year);
+        let modified_year = history
+            .as_ref()
+            .and_then(FileHistory::modified_year)
+            .unwrap_or(self.year);
+        let years = match &history {
+            Some(h) if !h.years.is_empty() => h.years_text(),
+            _ => self.year.to_string(),
+        };
+
+        // Existing years are read from the file once the rest is known
+        let year = policy_year(self.year_policy, created_year, self.year);
+
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let dir_name = match file_path.rsplit_once('/') {
+            Some((dir, _)) => dir.to_string(),
+            None => String::from("."),
+        };
+        let name_path = Path::new(&file_name);
+        let stem = name_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let ext = name_path
+            .extension()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut vars = TemplateVars {
+            file_name,
+            file_path: file_path.clone(),
+            dir_name,
+            stem,
+            ext,
+            date: self.date.clone(),
             year,
-            matched_filetypes_count: 0,
+            created_year: created_year.to_string(),
+            modified_year: modified_year.to_string(),
+            years,
+            authors,
+            variables,
+        };
+
+        // The file is read here to find its year, so its contents are kept
+        // for the command rather than read a second time.
+        let mut contents = None;
+        if self.year_policy == YearPolicy::Preserve {
+            contents = fs::read(header_file_path(entry.path(), &file_type_config)).ok();
+            let current_lines = license.get_formatted_lines(&vars)?;
+            let current_header_text = License::get_header_text(&current_lines, &file_type_config);
+            if let Some(year) = contents.as_deref().and_then(|bytes| {
+                read_header_year(
+                    bytes,
+                    &file_type_config,
+                    &current_header_text,
+                    &header_pattern,
+                )
+            }) {
+                vars.year = year;
+            }
         }
+
+        let formatted_license_lines = license.get_formatted_lines(&vars)?;
+
+        let header_text = License::get_header_text(&formatted_license_lines, &file_type_config);
+
+        Ok(FileData {
+            file_path,
+            formatted_license_lines,
+            header_text,
+            header_pattern,
+            entry,
+            file_type_config,
+            contents,
+        })
     }
 }
 
+impl FileWalk {
+    /// Finds the license for a file, which is the nearest `.licensesnip` in
+    /// its directory or the directories above it, up to the project root.
+    fn get_license(&self, path: &Path) -> Arc<License> {
+        if !self.hierarchical {
+            return self.license.clone();
+        }
+
+        let dir = match fs::canonicalize(path) {
+            Ok(p) => match p.parent() {
+                Some(dir) => dir.to_path_buf(),
+                None => return self.license.clone(),
+            },
+            Err(_) => return self.license.clone(),
+        };
+
+        self.get_dir_license(&dir)
+    }
+
+    fn get_dir_license(&self, dir: &Path) -> Arc<License> {
+        if let Some(license) = self.licenses.lock().unwrap().get(dir) {
+            return license.clone();
+        }
+
+        let license = if dir == self.root || !dir.starts_with(&self.root) {
+            self.license.clone()
+        } else {
+            match read_license(dir) {
+                Ok(license) => {
+                    let license_path = dir.join(LICENSE_PATH);
+                    f_check_license_variables(
+                        &license,
+                        &self.variables,
+                        &license_path.to_string_lossy(),
+                    );
+                    Arc::new(license)
+                }
+                Err(_) => match dir.parent() {
+                    Some(parent) => self.get_dir_license(parent),
+                    None => self.license.clone(),
+                },
+            }
+        };
+
+        self.licenses
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), license.clone());
+        license
+    }
+}
+
+/// Settings from the overrides that match a file, which replace the walk's own.
+#[derive(Default)]
+struct FileOverrides {
+    license: Option<PathBuf>,
+    variables: Option<HashMap<String, String>>,
+}
+
+impl FileWalk {
+    /// Applies the overrides that match a file on top of its file type's
+    /// settings. Later overrides take precedence over earlier ones.
+    fn apply_overrides(
+        &self,
+        file_path: &str,
+        file_type_config: &FileTypeConfig,
+    ) -> (FileTypeConfig, FileOverrides) {
+        let mut config = file_type_config.clone();
+        let mut file_overrides = FileOverrides::default();
+
+        for o in self
+            .overrides
+            .iter()
+            .filter(|o| o.paths.is_match(file_path))
+        {
+            config = FileTypeConfig::assign_partial(&config, &o.file_type);
+
+            if let Some(license) = &o.license {
+                file_overrides.license = Some(license.clone());
+            }
+
+            if let Some(variables) = &o.variables {
+                let merged = file_overrides
+                    .variables
+                    .get_or_insert_with(|| self.variables.clone());
+                for (name, value) in variables {
+                    merged.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+
+        (config, file_overrides)
+    }
+
+    /// Reads the license template of an override, exiting with an error if it
+    /// can't be read.
+    fn get_override_license(&self, license_path: &Path) -> Arc<License> {
+        if let Some(license) = self.override_licenses.lock().unwrap().get(license_path) {
+            return license.clone();
+        }
+
+        let license = match fs::read_to_string(self.root.join(license_path)) {
+            Ok(text) => Arc::new(License::new(text.trim().to_string())),
+            Err(e) => {
+                let error = format!(
+                    "Error: Couldn't read the license {} from your overrides: {}",
+                    license_path.display(),
+                    e
+                );
+                println!("{}", error.red());
+                std::process::exit(exitcode::CONFIG);
+            }
+        };
+
+        self.override_licenses
+            .lock()
+            .unwrap()
+            .insert(license_path.to_path_buf(), license.clone());
+        license
+    }
+}
+
+/// Checks the placeholders of a license, exiting with an error if any are unknown.
+fn f_check_license_variables(
+    license: &License,
+    variables: &HashMap<String, String>,
+    license_path: &str,
+) {
+    if let Err(e) = license.check_variables(variables) {
+        println!("{}", format!("Error in {}: {}", license_path, e).red());
+        std::process::exit(exitcode::CONFIG);
+    }
+}
+
+/// Gets a path relative to the project root, with `/` separators.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let absolute = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let relative = absolute.strip_prefix(root).unwrap_or(path);
+
+    relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Finds a file's type from its name, or from the interpreter in its shebang
+/// line if its name doesn't match.
+fn find_file_type<'a>(
+    file_types: &'a FileTypeMatcher,
+    path: &Path,
+    file_name: &str,
+    file_path: &str,
+) -> Option<(&'a str, &'a FileTypeConfig)> {
+    file_types.get(file_name, file_path).or_else(|| {
+        read_interpreter(path).and_then(|interpreter| file_types.get_by_interpreter(&interpreter))
+    })
+}
+
+/// Reads the interpreter named in a script's shebang line, such as `python3` in
+/// `#!/usr/bin/env python3` or `bash` in `#!/bin/bash`.
+fn read_interpreter(path: &Path) -> Option<String> {
+    let mut start = [0; 256];
+    let len = fs::File::open(path).ok()?.read(&mut start).ok()?;
+    let first_line = start[..len].split(|&b| b == b'\n').next()?;
+    let line = std::str::from_utf8(first_line).ok()?.strip_prefix("#!")?;
+    if line.starts_with('[') {
+        // Rust's `#![...]`
+        return None;
+    }
+
+    let mut words = line.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        // Skip options such as `env -S`
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    Some(interpreter.to_string())
+}
+
 pub struct FileData {
+    /// Path from the project root, as in `%FILEPATH%`
+    pub file_path: String,
     pub formatted_license_lines: Vec<String>,
     pub header_text: String,
-    pub entry: ignore::DirEntry,
+    pub header_pattern: Regex,
+    pub entry: DirEntry,
     pub file_type_config: FileTypeConfig,
+    /// Contents of the file holding the header, when they were already read
+    /// to find the existing year
+    pub contents: Option<Vec<u8>>,
 }
 
-impl Iterator for FileWalk {
-    type Item = FileData;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(n) = self.ignore_walk.next() {
-            match n {
-                Ok(entry) => {
-                    match entry.file_type() {
-                        Some(t) => {
-                            if !t.is_file() {
-                                return self.next();
-                            }
-                        }
-                        None => return self.next(),
-                    }
+/// What happened to a file during a walk.
+pub enum FileOutcome<R> {
+    /// The file was processed, giving this result
+    Processed(R),
+    /// The file was left alone, for this reason. Only reported in verbose mode.
+    Skipped(String),
+    /// The file couldn't be processed, for this reason
+    Failed(String),
+    /// The file is a sidecar whose own file no longer exists. Holds a warning
+    /// about it.
+    OrphanedSidecar(String),
+}
 
-                    // Get file extension
-                    let file_name = entry.file_name().to_string_lossy();
-                    let ext = match file_name.split('.').last() {
-                        Some(e) => e,
-                        None => {
-                            if self.verbose {
-                                println!(
-                                    "(skipped) Invalid file extension - {}",
-                                    entry.path().display()
-                                )
-                            }
-                            return self.next();
-                        }
-                    };
-
-                    let file_type_config = match self.filetype_map.get(ext) {
-                        Some(e) => {
-                            self.matched_filetypes_count += 1;
-                            e
-                        }
-                        None => {
-                            // No configuration for this file type
-                            if self.verbose {
-                                println!(
-                                    "(skipped) No file type configuration found for .{} - {}",
-                                    ext,
-                                    entry.path().display()
-                                );
-                            }
-
-                            return self.next();
-                        }
-                    };
-
-                    if !file_type_config.enable {
-                        // Disabled for this filetype
-                        if self.verbose {
-                            println!(
-                                "(skipped) Inserting header is disabled for .{} files - {}",
-                                ext,
-                                entry.path().display()
-                            )
-                        }
-                        return self.next();
-                    }
+pub struct FileReport<R> {
+    pub path: PathBuf,
+    pub outcome: FileOutcome<R>,
+}
 
-                    let raw_license_lines = self.license.get_lines();
+/// Prints why a file was skipped or failed. Processed files are left to the
+/// command to report.
+pub fn f_print_outcome<R>(outcome: &FileOutcome<R>) {
+    match outcome {
+        FileOutcome::Processed(_) => {}
+        FileOutcome::Skipped(reason) => println!("{}", reason),
+        FileOutcome::Failed(reason) => println!("{}", reason.red()),
+        FileOutcome::OrphanedSidecar(warning) => println!("{}", warning.yellow()),
+    }
+}
 
-                    let formatted_license_lines =
-                        License::get_formatted_lines(&raw_license_lines, &file_name, self.year);
+/// Files seen so far in a walk, when symlinks are followed.
+#[derive(Default)]
+struct Visited {
+    /// Symlinks to files, which are processed once the walk is done
+    links: Mutex<Vec<DirEntry>>,
+    /// Canonical paths of the files that have been processed
+    files: Mutex<HashSet<PathBuf>>,
+}
 
-                    let header_text =
-                        License::get_header_text(&formatted_license_lines, file_type_config);
+impl FileWalk {
+    fn visit<R, F>(
+        &self,
+        entry: Result<DirEntry, ignore::Error>,
+        process: &F,
+        visited: &Visited,
+    ) -> Option<FileReport<R>>
+    where
+        F: Fn(FileData) -> R,
+    {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                return Some(FileReport {
+                    path: PathBuf::new(),
+                    outcome: FileOutcome::Failed(format!("ERROR: {}", err)),
+                })
+            }
+        };
+        let path = entry.path().to_path_buf();
+        let report = |outcome| {
+            Some(FileReport {
+                path: path.clone(),
+                outcome,
+            })
+        };
+        let skip = |reason: String| {
+            if self.verbose {
+                report(FileOutcome::Skipped(reason))
+            } else {
+                None
+            }
+        };
 
-                    return Some(FileData {
-                        formatted_license_lines,
-                        header_text,
-                        entry,
-                        file_type_config: file_type_config.clone(),
-                    });
+        if entry.path_is_symlink() {
+            match self.symlinks {
+                SymlinkPolicy::Skip => {
+                    return skip(format!("(skipped) Symlink - {}", path.display()));
                 }
-                Err(err) => {
-                    println!("ERROR: {}", err);
-                    return self.next();
+                SymlinkPolicy::Follow => {
+                    if path.is_file() {
+                        visited.links.lock().unwrap().push(entry);
+                    }
+                    return None;
+                }
+                SymlinkPolicy::Error => {
+                    let error = format!(
+                        "Error: {} is a symlink. Set \"symlinks\" to \"skip\" or \"follow\" in your config to allow symlinks.",
+                        path.display()
+                    );
+                    println!("{}", error.red());
+                    std::process::exit(exitcode::DATAERR);
                 }
             }
+        } else {
+            match entry.file_type() {
+                Some(t) => {
+                    if !t.is_file() {
+                        return None;
+                    }
+                }
+                None => return None,
+            }
+            if self.symlinks == SymlinkPolicy::Follow {
+                let file = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                visited.files.lock().unwrap().insert(file);
+            }
         }
-        None
+
+        self.visit_file(entry, process)
+    }
+
+    /// Processes a file that the walk has found, or a file that a symlink
+    /// points to.
+    fn visit_file<R, F>(&self, entry: DirEntry, process: &F) -> Option<FileReport<R>>
+    where
+        F: Fn(FileData) -> R,
+    {
+        let path = entry.path().to_path_buf();
+        let report = |outcome| {
+            Some(FileReport {
+                path: path.clone(),
+                outcome,
+            })
+        };
+        let skip = |reason: String| {
+            if self.verbose {
+                report(FileOutcome::Skipped(reason))
+            } else {
+                None
+            }
+        };
+
+        if let Some(owner) = self.sidecar_owner(&path) {
+            if owner.symlink_metadata().is_err() {
+                return report(FileOutcome::OrphanedSidecar(format!(
+                    "(warning) Orphaned sidecar, the file it belongs to doesn't exist - {}",
+                    path.display()
+                )));
+            }
+            // The header is checked and written along with the file itself
+            return skip(format!(
+                "(skipped) Sidecar of {} - {}",
+                owner.display(),
+                path.display()
+            ));
+        }
+
+        let file_name = entry.file_name().to_string_lossy();
+        let file_path = relative_path(&self.root, &path);
+        let file_type = find_file_type(&self.file_types, &path, &file_name, &file_path);
+        let (file_type, file_type_config) = match file_type {
+            Some(e) => {
+                self.matched_filetypes_count.fetch_add(1, Ordering::Relaxed);
+                e
+            }
+            None => {
+                // No configuration for this file type
+                return skip(format!(
+                    "(skipped) No file type configuration found - {}",
+                    path.display()
+                ));
+            }
+        };
+
+        let (file_type_config, file_overrides) = self.apply_overrides(&file_path, file_type_config);
+
+        if !file_type_config.enable {
+            // Disabled for this filetype
+            return skip(format!(
+                "(skipped) Inserting header is disabled for \"{}\" files - {}",
+                file_type,
+                path.display()
+            ));
+        }
+
+        // Sidecars hold plain text rather than comments, and so do Markdown
+        // cells, where a `# ` line comment would make each line a heading
+        let plain_text = match file_type_config.mode {
+            FileMode::Inline => false,
+            FileMode::Notebook => file_type_config.notebook_cell == NotebookCell::Markdown,
+            FileMode::Sidecar => true,
+        };
+        let file_type_config = if plain_text {
+            FileTypeConfig {
+                before_block: String::new(),
+                after_block: String::new(),
+                before_line: String::new(),
+                after_line: String::new(),
+                ..file_type_config
+            }
+        } else {
+            file_type_config
+        };
+
+        match self.get_file_data(entry, file_path, file_type_config, file_overrides) {
+            Ok(file_data) => report(FileOutcome::Processed(process(file_data))),
+            Err(e) => report(FileOutcome::Failed(format!("Error: {}", e))),
+        }
+    }
+}
+
+impl FileWalk {
+    /// Gets the file that a sidecar belongs to, if that file is in sidecar
+    /// mode. A file that no longer exists counts if its name would put it in
+    /// sidecar mode.
+    fn sidecar_owner(&self, path: &Path) -> Option<PathBuf> {
+        self.sidecar_license.as_ref()?;
+        let owner = path
+            .to_str()
+            .and_then(|path| path.strip_suffix(SIDECAR_SUFFIX))
+            .map(PathBuf::from)?;
+        let file_name = owner.file_name().and_then(|name| name.to_str())?;
+
+        let sidecar_relative = relative_path(&self.root, path);
+        let relative = sidecar_relative
+            .strip_suffix(SIDECAR_SUFFIX)
+            .unwrap_or(&sidecar_relative);
+        let (_, file_type_config) = if owner.symlink_metadata().is_ok() {
+            find_file_type(&self.file_types, &owner, file_name, relative)?
+        } else {
+            self.file_types.get(file_name, relative)?
+        };
+        let (file_type_config, _) = self.apply_overrides(relative, file_type_config);
+
+        (file_type_config.enable && file_type_config.mode == FileMode::Sidecar).then_some(owner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::license::sidecar_path;
+    use crate::{
+        config::PartialConfig,
+        license::{
+            AddToFileErr, AddToFileResult, RemoveFromFileErr, RemoveFromFileResult, UpdateFileErr,
+            UpdateFileResult,
+        },
+        notebook::find_header_cell,
+    };
+
+    const NOTEBOOK: &str = r#"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "metadata": {},
+   "source": ["print(1)"]
+  }
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"#;
+
+    fn builtin_config() -> Config {
+        let Ok(base) = PartialConfig::base() else {
+            panic!("base config should load");
+        };
+        Config::assign_partial(&Config::default(), &base)
+    }
+
+    fn builtin_matcher() -> FileTypeMatcher {
+        builtin_config().get_filetype_matcher().unwrap()
+    }
+
+    fn walk(root: &Path, config: Config, year: i32) -> FileWalk {
+        let license = License::new(String::from("Copyright %YEAR% Acme\nMIT License"));
+        let root = root.to_path_buf();
+        FileWalk::new(root.clone(), root, config, license, year, false, Some(4))
+    }
+
+    /// Gets the result of each file a walk processed, failing on any other
+    /// outcome.
+    fn results<R>(reports: Vec<FileReport<R>>) -> Vec<R> {
+        reports
+            .into_iter()
+            .map(|report| match report.outcome {
+                FileOutcome::Processed(result) => result,
+                _ => panic!("{} wasn't processed", report.path.display()),
+            })
+            .collect()
+    }
+
+    fn add(walk: &FileWalk) -> Vec<Result<AddToFileResult, AddToFileErr>> {
+        results(walk.run(|data| {
+            License::add_to_file(
+                &data.entry,
+                &data.file_type_config,
+                &data.header_text,
+                &data.header_pattern,
+                data.contents,
+            )
+        }))
+    }
+
+    fn check(walk: &FileWalk) -> Vec<Result<bool, AddToFileErr>> {
+        results(walk.run(|data| {
+            License::check_file(
+                &data.entry,
+                &data.file_type_config,
+                &data.header_pattern,
+                data.contents,
+            )
+        }))
+    }
+
+    fn update(walk: &FileWalk) -> Vec<Result<UpdateFileResult, UpdateFileErr>> {
+        results(walk.run(|data| {
+            License::update_file(
+                &data.entry,
+                &data.file_type_config,
+                &data.header_text,
+                &data.header_pattern,
+                data.contents,
+            )
+        }))
+    }
+
+    fn remove(walk: &FileWalk) -> Vec<Result<RemoveFromFileResult, RemoveFromFileErr>> {
+        results(walk.run(|data| {
+            License::remove_from_file(
+                &data.entry,
+                &data.file_type_config,
+                &data.header_pattern,
+                data.contents,
+            )
+        }))
+    }
+
+    #[test]
+    fn reads_interpreters_from_shebangs() {
+        let dir = tempfile::tempdir().unwrap();
+        let interpreter = |shebang: &str| {
+            let path = dir.path().join("script");
+            fs::write(&path, format!("{}\necho hello\n", shebang)).unwrap();
+            read_interpreter(&path)
+        };
+
+        assert_eq!(interpreter("#!/usr/bin/env bash").as_deref(), Some("bash"));
+        assert_eq!(interpreter("#!/bin/bash").as_deref(), Some("bash"));
+        assert_eq!(interpreter("#! /bin/sh -e").as_deref(), Some("sh"));
+        assert_eq!(
+            interpreter("#!/usr/bin/python3.12").as_deref(),
+            Some("python3.12")
+        );
+        assert_eq!(
+            interpreter("#!/usr/bin/env -S deno run").as_deref(),
+            Some("deno")
+        );
+        assert_eq!(interpreter("#![no_std]"), None);
+        assert_eq!(interpreter("echo hello"), None);
+        assert_eq!(interpreter("#!/usr/bin/env"), None);
+    }
+
+    #[test]
+    fn scripts_named_like_an_extension_use_their_shebang() {
+        let matcher = builtin_matcher();
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("bin")).unwrap();
+
+        for name in ["go", "m", "mm"] {
+            let path = dir.path().join("bin").join(name);
+            fs::write(&path, "#!/bin/sh\necho hello\n").unwrap();
+
+            let file_path = format!("bin/{}", name);
+            let Some((file_type, config)) = find_file_type(&matcher, &path, name, &file_path)
+            else {
+                panic!("{} matched no file type", file_path);
+            };
+            assert_eq!(file_type, "Shell", "{}", file_path);
+            assert_eq!(config.before_line, "# ", "{}", file_path);
+        }
+    }
+
+    #[test]
+    fn writes_markdown_cells_without_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("n.ipynb");
+        fs::write(&path, NOTEBOOK).unwrap();
+
+        let mut config = builtin_config();
+        config.file_types.insert(
+            String::from("ipynb"),
+            FileTypeConfig {
+                before_line: String::from("# "),
+                mode: FileMode::Notebook,
+                notebook_cell: NotebookCell::Markdown,
+                ..FileTypeConfig::default()
+            },
+        );
+        let source = |path: &Path| {
+            let cell = find_header_cell(&fs::read(path).unwrap()).unwrap();
+            cell.map(|cell| cell.source)
+        };
+
+        let old = walk(dir.path(), config.clone(), 2024);
+        assert!(matches!(add(&old)[..], [Ok(AddToFileResult::Added)]));
+        assert_eq!(
+            source(&path).as_deref(),
+            Some("Copyright 2024 Acme\nMIT License")
+        );
+        assert!(matches!(check(&old)[..], [Ok(true)]));
+        assert!(matches!(add(&old)[..], [Ok(AddToFileResult::NoChange)]));
+
+        let new = walk(dir.path(), config, 2025);
+        assert!(matches!(update(&new)[..], [Ok(UpdateFileResult::Updated)]));
+        assert_eq!(
+            source(&path).as_deref(),
+            Some("Copyright 2025 Acme\nMIT License")
+        );
+        assert!(matches!(
+            remove(&new)[..],
+            [Ok(RemoveFromFileResult::Removed)]
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), NOTEBOOK);
+    }
+
+    fn sidecar_config() -> Config {
+        let mut config = builtin_config();
+        config.spdx_id = Some(String::from("MIT"));
+        config.copyright_holder = Some(String::from("Acme"));
+        config.file_types.insert(
+            String::from("png"),
+            FileTypeConfig {
+                mode: FileMode::Sidecar,
+                ..FileTypeConfig::default()
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn writes_headers_to_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("logo.png");
+        fs::write(&image, b"\x89PNG").unwrap();
+        let sidecar = sidecar_path(&image);
+
+        let walk = walk(dir.path(), sidecar_config(), 2024);
+        assert!(matches!(check(&walk)[..], [Ok(false)]));
+        assert!(matches!(add(&walk)[..], [Ok(AddToFileResult::Added)]));
+        assert_eq!(fs::read(&image).unwrap(), b"\x89PNG");
+        assert_eq!(
+            fs::read_to_string(&sidecar).unwrap(),
+            "SPDX-FileCopyrightText: 2024 Acme\nSPDX-License-Identifier: MIT\n"
+        );
+        // The sidecar itself is walked, but left to the image
+        assert!(matches!(check(&walk)[..], [Ok(true)]));
+        assert!(matches!(add(&walk)[..], [Ok(AddToFileResult::NoChange)]));
+
+        assert!(matches!(
+            remove(&walk)[..],
+            [Ok(RemoveFromFileResult::Removed)]
+        ));
+        assert!(!sidecar.exists());
+        assert_eq!(fs::read(&image).unwrap(), b"\x89PNG");
+    }
+
+    #[test]
+    fn reports_sidecars_as_skipped_when_verbose() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("logo.png");
+        fs::write(&image, b"\x89PNG").unwrap();
+        fs::write(sidecar_path(&image), "SPDX-License-Identifier: MIT\n").unwrap();
+
+        let license = License::new(String::from("Copyright %YEAR% Acme"));
+        let root = dir.path().to_path_buf();
+        let walk = FileWalk::new(
+            root.clone(),
+            root,
+            sidecar_config(),
+            license,
+            2024,
+            true,
+            None,
+        );
+        let skipped: Vec<_> = walk
+            .run(|_| ())
+            .into_iter()
+            .filter_map(|report| match report.outcome {
+                FileOutcome::Skipped(reason) => Some(reason),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(skipped.len(), 1);
+        assert!(
+            skipped[0].starts_with("(skipped) Sidecar of "),
+            "{}",
+            skipped[0]
+        );
+    }
+
+    #[test]
+    fn reports_orphaned_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("logo.png"), b"\x89PNG").unwrap();
+        let orphan = dir.path().join("gone.png.license");
+        fs::write(&orphan, "SPDX-License-Identifier: MIT\n").unwrap();
+        // Not a sidecar, since text files aren't in sidecar mode
+        fs::write(dir.path().join("notes.txt.license"), "MIT\n").unwrap();
+
+        let walk = walk(dir.path(), sidecar_config(), 2024);
+        let orphans: Vec<_> = walk
+            .run(|_| ())
+            .into_iter()
+            .filter(|report| matches!(report.outcome, FileOutcome::OrphanedSidecar(_)))
+            .map(|report| report.path)
+            .collect();
+        assert_eq!(orphans, [orphan]);
+    }
+
+    #[test]
+    fn keeps_the_year_of_an_existing_header_when_preserving() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.sh");
+        let new = dir.path().join("new.sh");
+        fs::write(&old, "# Copyright 2019 Acme\n# MIT License\n\necho old\n").unwrap();
+        fs::write(&new, "echo new\n").unwrap();
+
+        let mut config = builtin_config();
+        config.year_policy = YearPolicy::Preserve;
+        let walk = walk(dir.path(), config, 2025);
+        assert!(add(&walk).iter().all(Result::is_ok));
+        assert!(check(&walk).iter().all(|result| matches!(result, Ok(true))));
+
+        let old = fs::read_to_string(&old).unwrap();
+        assert_eq!(old, "# Copyright 2019 Acme\n# MIT License\n\necho old\n");
+        let new = fs::read_to_string(&new).unwrap();
+        assert_eq!(new, "# Copyright 2025 Acme\n# MIT License\n\necho new\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn edits_a_symlinked_file_once() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir(&root).unwrap();
+        fs::write(root.join("run.sh"), "echo run\n").unwrap();
+        symlink(root.join("run.sh"), root.join("link.sh")).unwrap();
+        // Two links to a file outside the walk
+        fs::write(dir.path().join("shared.sh"), "echo shared\n").unwrap();
+        symlink(dir.path().join("shared.sh"), root.join("a.sh")).unwrap();
+        symlink(dir.path().join("shared.sh"), root.join("b.sh")).unwrap();
+
+        let mut config = builtin_config();
+        config.symlinks = SymlinkPolicy::Follow;
+        config.parallel = true;
+        let walk = walk(&root, config, 2024);
+
+        let reports = walk.run(|data| {
+            License::add_to_file(
+                &data.entry,
+                &data.file_type_config,
+                &data.header_text,
+                &data.header_pattern,
+                data.contents,
+            )
+        });
+        let paths: Vec<_> = reports
+            .iter()
+            .map(|report| report.path.strip_prefix(&root).unwrap())
+            .collect();
+        assert_eq!(paths, [Path::new("a.sh"), Path::new("run.sh")]);
+        for report in &reports {
+            assert!(matches!(
+                report.outcome,
+                FileOutcome::Processed(Ok(AddToFileResult::Added))
+            ));
+        }
+
+        let header = "# Copyright 2024 Acme\n# MIT License\n\n";
+        let run = fs::read_to_string(root.join("run.sh")).unwrap();
+        assert_eq!(run, format!("{}echo run\n", header));
+        let shared = fs::read_to_string(dir.path().join("shared.sh")).unwrap();
+        assert_eq!(shared, format!("{}echo shared\n", header));
     }
 }
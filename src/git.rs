@@ -0,0 +1,332 @@
+// git.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::year::format_year_range;
+
+/// What the local git history says about a single file.
+#[derive(Debug, Clone, Default)]
+pub struct FileHistory {
+    /// Every year the file was committed in.
+    pub years: BTreeSet<i32>,
+    /// Commit authors, in order of their first commit to the file.
+    pub authors: Vec<String>,
+}
+
+impl FileHistory {
+    pub fn created_year(&self) -> Option<i32> {
+        self.years.iter().next().copied()
+    }
+
+    pub fn modified_year(&self) -> Option<i32> {
+        self.years.iter().next_back().copied()
+    }
+
+    /// Formats the commit years with consecutive years collapsed into ranges,
+    /// e.g. `2019, 2021-2023`.
+    pub fn years_text(&self) -> String {
+        let mut spans = Vec::<String>::new();
+        let mut years = self.years.iter().copied().peekable();
+
+        while let Some(first) = years.next() {
+            let mut last = first;
+            while years.peek() == Some(&(last + 1)) {
+                last += 1;
+                years.next();
+            }
+            spans.push(format_year_range(first, last));
+        }
+
+        spans.join(", ")
+    }
+
+    pub fn authors_text(&self) -> String {
+        self.authors.join(", ")
+    }
+
+    fn add_commit(&mut self, year: i32, author: &str) {
+        self.years.insert(year);
+        if !self.authors.iter().any(|a| a == author) {
+            self.authors.push(author.to_string());
+        }
+    }
+}
+
+/// Reads file histories from the local git repository. The whole history of a
+/// repository is read with a single `git log` the first time one of its files
+/// is looked up, so walking a large repository stays fast.
+#[derive(Default)]
+pub struct GitHistory {
+    /// Repository root of each directory that has been looked up.
+    repo_roots: HashMap<PathBuf, Option<PathBuf>>,
+    /// File histories of each repository, keyed by path relative to its root.
+    repos: HashMap<PathBuf, HashMap<PathBuf, FileHistory>>,
+    user_name: Option<Option<String>>,
+}
+
+impl GitHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the history of a file. Returns `None` if the file isn't inside a
+    /// git repository or hasn't been committed yet.
+    pub fn file_history(&mut self, path: &Path) -> Option<&FileHistory> {
+        let path = fs::canonicalize(path).ok()?;
+        let dir = path.parent()?.to_path_buf();
+
+        let root = match self.repo_roots.get(&dir) {
+            Some(root) => root.clone(),
+            None => {
+                let root = repo_root(&dir);
+                self.repo_roots.insert(dir, root.clone());
+                root
+            }
+        }?;
+
+        if !self.repos.contains_key(&root) {
+            let histories = read_histories(&root).unwrap_or_default();
+            self.repos.insert(root.clone(), histories);
+        }
+
+        let relative_path = path.strip_prefix(&root).ok()?;
+        self.repos.get(&root)?.get(relative_path)
+    }
+
+    /// Gets the git user name configured for the project at `root`, who will
+    /// be the author of any file that hasn't been committed yet.
+    pub fn user_name(&mut self, root: &Path) -> Option<&str> {
+        if self.user_name.is_none() {
+            self.user_name = Some(git_output(root, &["config", "user.name"]));
+        }
+        self.user_name.as_ref()?.as_deref()
+    }
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn repo_root(dir: &Path) -> Option<PathBuf> {
+    let root = git_output(dir, &["rev-parse", "--show-toplevel"])?;
+    fs::canonicalize(root).ok()
+}
+
+/// Marks the start of each commit in the `git log` output.
+const COMMIT_MARKER: char = '\u{0}';
+
+struct Commit<'a> {
+    year: i32,
+    author: &'a str,
+    changes: Vec<&'a str>,
+}
+
+fn read_histories(root: &Path) -> Option<HashMap<PathBuf, FileHistory>> {
+    let log = git_output(
+        root,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--name-status",
+            "-M",
+            "--date=format:%Y",
+            "--format=%x00%ad%x00%an",
+        ],
+    )?;
+
+    parse_histories(&log)
+}
+
+/// Builds file histories from `git log --name-status` output, newest commit
+/// first, with each commit's header marked by `COMMIT_MARKER`.
+fn parse_histories(log: &str) -> Option<HashMap<PathBuf, FileHistory>> {
+    let mut commits = Vec::<Commit>::new();
+    for line in log.lines() {
+        if let Some(header) = line.strip_prefix(COMMIT_MARKER) {
+            let (year, author) = header.split_once(COMMIT_MARKER)?;
+            commits.push(Commit {
+                year: year.parse().ok()?,
+                author,
+                changes: Vec::new(),
+            });
+        } else if !line.is_empty() {
+            commits.last_mut()?.changes.push(line);
+        }
+    }
+
+    // Replay the history oldest first so renames carry a file's history over
+    // to its new path.
+    let mut histories = HashMap::<PathBuf, FileHistory>::new();
+    for commit in commits.iter().rev() {
+        for change in &commit.changes {
+            let mut fields = change.split('\t');
+            let status = fields.next().unwrap_or_default();
+            let path = match (fields.next(), fields.next()) {
+                (Some(old_path), Some(new_path)) if status.starts_with('R') => {
+                    let history = histories.remove(Path::new(old_path)).unwrap_or_default();
+                    histories.insert(PathBuf::from(new_path), history);
+                    new_path
+                }
+                (Some(_), Some(new_path)) => new_path,
+                (Some(path), None) => path,
+                _ => continue,
+            };
+
+            if status.starts_with('D') {
+                histories.remove(Path::new(path));
+                continue;
+            }
+
+            histories
+                .entry(PathBuf::from(path))
+                .or_default()
+                .add_commit(commit.year, commit.author);
+        }
+    }
+
+    Some(histories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(commits: &[(&str, &str, &[&str])]) -> String {
+        let mut text = String::new();
+        for (year, author, changes) in commits {
+            text.push_str(&format!("\0{}\0{}\n\n", year, author));
+            for change in *changes {
+                text.push_str(change);
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn collects_years_and_authors() {
+        let log = log(&[
+            ("2023", "Ana", &["M\tsrc/main.rs"]),
+            ("2021", "Ben", &["M\tsrc/main.rs", "A\tREADME.md"]),
+            ("2019", "Ana", &["A\tsrc/main.rs"]),
+        ]);
+        let histories = parse_histories(&log).unwrap();
+
+        let main = &histories[Path::new("src/main.rs")];
+        assert_eq!(main.created_year(), Some(2019));
+        assert_eq!(main.modified_year(), Some(2023));
+        assert_eq!(main.years_text(), "2019, 2021, 2023");
+        assert_eq!(main.authors_text(), "Ana, Ben");
+        assert_eq!(histories[Path::new("README.md")].authors, ["Ben"]);
+    }
+
+    #[test]
+    fn carries_history_across_renames() {
+        let log = log(&[
+            ("2022", "Cy", &["M\tnew/name.rs"]),
+            ("2021", "Ben", &["R087\told.rs\tnew/name.rs"]),
+            ("2020", "Ana", &["A\told.rs"]),
+        ]);
+        let histories = parse_histories(&log).unwrap();
+
+        assert!(!histories.contains_key(Path::new("old.rs")));
+        let renamed = &histories[Path::new("new/name.rs")];
+        assert_eq!(renamed.years_text(), "2020-2022");
+        assert_eq!(renamed.authors_text(), "Ana, Ben, Cy");
+    }
+
+    #[test]
+    fn forgets_deleted_files() {
+        let log = log(&[
+            ("2024", "Cy", &["A\tgone.rs"]),
+            ("2022", "Ben", &["D\tgone.rs"]),
+            ("2020", "Ana", &["A\tgone.rs", "A\tkept.rs"]),
+        ]);
+        let histories = parse_histories(&log).unwrap();
+
+        // Added back after being deleted, so only the new history counts
+        let readded = &histories[Path::new("gone.rs")];
+        assert_eq!(readded.years_text(), "2024");
+        assert_eq!(readded.authors_text(), "Cy");
+        assert!(histories.contains_key(Path::new("kept.rs")));
+    }
+
+    #[test]
+    fn keeps_copies_separate() {
+        let log = log(&[
+            ("2021", "Ben", &["C100\ta.rs\tb.rs"]),
+            ("2020", "Ana", &["A\ta.rs"]),
+        ]);
+        let histories = parse_histories(&log).unwrap();
+
+        assert_eq!(histories[Path::new("a.rs")].authors_text(), "Ana");
+        assert_eq!(histories[Path::new("b.rs")].authors_text(), "Ben");
+    }
+
+    #[test]
+    fn rejects_malformed_log() {
+        assert!(parse_histories("M\tfile.rs\n").is_none());
+        assert!(parse_histories("\0twenty\0Ana\n").is_none());
+        assert!(parse_histories("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_the_user_name_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["config", "user.name", "Project User"],
+        ] {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        let mut history = GitHistory::new();
+        assert_eq!(history.user_name(dir.path()), Some("Project User"));
+    }
+}
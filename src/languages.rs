@@ -0,0 +1,192 @@
+// languages.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use regex::escape;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::config::{FileMode, FileTypeConfig, LinePatterns, Placement};
+
+pub static LANGUAGES: &str = include_str!("languages.jsonc");
+
+/// An entry of the language database, keyed by the language's name. Every
+/// field is optional so that an entry in a config only needs the fields it
+/// changes.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Language {
+    pub extensions: Option<Vec<String>>,
+    /// Exact file names, such as `Makefile`
+    pub filenames: Option<Vec<String>>,
+    /// Interpreters named in the shebang lines of scripts in this language
+    pub interpreters: Option<Vec<String>>,
+    /// Start of a line comment, such as `//`
+    pub line_comment: Option<String>,
+    /// Start and end of a block comment, such as `["/*", "*/"]`
+    pub block_comment: Option<(String, String)>,
+    /// Leading lines that stay above the header, besides modelines
+    pub preamble: Option<LinePatterns>,
+    pub placement: Option<Placement>,
+    pub mode: Option<FileMode>,
+    pub enable: Option<bool>,
+}
+
+impl Language {
+    pub fn assign(target: &Self, source: &Self) -> Self {
+        let mut new = target.clone();
+
+        if let Some(extensions) = &source.extensions {
+            new.extensions = Some(extensions.clone());
+        }
+        if let Some(filenames) = &source.filenames {
+            new.filenames = Some(filenames.clone());
+        }
+        if let Some(interpreters) = &source.interpreters {
+            new.interpreters = Some(interpreters.clone());
+        }
+        if let Some(line_comment) = &source.line_comment {
+            new.line_comment = Some(line_comment.to_string());
+        }
+        if let Some(block_comment) = &source.block_comment {
+            new.block_comment = Some(block_comment.clone());
+        }
+        if let Some(preamble) = &source.preamble {
+            new.preamble = Some(preamble.clone());
+        }
+        if let Some(placement) = source.placement {
+            new.placement = Some(placement);
+        }
+        if let Some(mode) = source.mode {
+            new.mode = Some(mode);
+        }
+        if let Some(enable) = source.enable {
+            new.enable = Some(enable);
+        }
+
+        new
+    }
+
+    /// The line comment, unless it was set to `""` to use the block comment.
+    fn line_comment(&self) -> Option<&str> {
+        self.line_comment.as_deref().filter(|line| !line.is_empty())
+    }
+
+    /// Whether the header can be written in this language's comments.
+    pub fn has_comments(&self) -> bool {
+        self.line_comment().is_some() || self.block_comment.is_some()
+    }
+
+    /// Gets the configuration for files in this language. The header is
+    /// written in line comments if the language has them, and in a block
+    /// comment otherwise. Emacs and Vim modelines in either kind of comment
+    /// stay above the header.
+    pub fn file_type_config(&self) -> FileTypeConfig {
+        let mut config = FileTypeConfig::default();
+        let mut preamble = self
+            .preamble
+            .as_ref()
+            .map(|preamble| preamble.patterns.clone())
+            .unwrap_or_default();
+
+        if let Some(line) = self.line_comment() {
+            config.before_line = format!("{} ", line);
+            preamble.extend(modelines(line, ""));
+        } else if let Some((open, close)) = &self.block_comment {
+            config.before_block = open.to_string();
+            config.before_line = String::from("  ");
+            config.after_block = close.to_string();
+        }
+        if let Some((open, close)) = &self.block_comment {
+            preamble.extend(modelines(open, close));
+        }
+
+        config.preamble =
+            LinePatterns::try_from(preamble).expect("modeline patterns should be valid regexes");
+        if let Some(placement) = self.placement {
+            config.placement = placement;
+        }
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(enable) = self.enable {
+            config.enable = enable;
+        }
+
+        config
+    }
+}
+
+/// Patterns for Emacs (`-*- mode: c -*-`) and Vim (`vim: set ts=4:`) modelines
+/// in a comment.
+fn modelines(open: &str, close: &str) -> Vec<String> {
+    let (open, close) = (escape(open), escape(close));
+    vec![
+        format!("{}.*-\\*-.*-\\*-.*{}", open, close),
+        format!("{}\\s*(vim?|ex):.*{}", open, close),
+    ]
+}
+
+/// Parses the language database compiled into the binary.
+pub fn builtin_languages() -> Result<HashMap<String, Language>, serde_json::Error> {
+    serde_json::from_str(LANGUAGES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_builtin_languages() {
+        let languages = builtin_languages().unwrap();
+        assert!(languages.len() > 100);
+
+        for (name, language) in &languages {
+            assert!(
+                language.extensions.is_some()
+                    || language.filenames.is_some()
+                    || language.interpreters.is_some(),
+                "{} matches no files",
+                name
+            );
+            assert!(
+                language.has_comments() || language.mode == Some(FileMode::Sidecar),
+                "{} has no comments",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn writes_line_comments_when_a_language_has_them() {
+        let languages = builtin_languages().unwrap();
+
+        let c = languages["C"].file_type_config();
+        assert_eq!(c.before_line, "// ");
+        assert_eq!(c.before_block, "");
+
+        let svelte = languages["Svelte"].file_type_config();
+        assert_eq!(svelte.before_block, "<!--");
+        assert_eq!(svelte.before_line, "  ");
+        assert_eq!(svelte.after_block, "-->");
+    }
+}
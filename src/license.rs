@@ -22,147 +22,308 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use crate::config::FileTypeConfig;
+use crate::config::{Config, FileMode, FileTypeConfig, Placement};
+use crate::notebook::{
+    add_header_cell, find_header_cell, remove_header_cell, replace_header_cell, NotebookErr,
+};
+use crate::spdx::{validate_expression, SpdxErr};
+use crate::template::{Template, TemplateErr, ENV_PREFIX};
+use crate::write::{create_file, write_file};
 use ignore::DirEntry;
-use mktemp::Temp;
+use regex::bytes::Regex;
 use std::{
-    fs,
-    fs::File,
-    io::Write,
-    io::{self, BufRead, BufReader, Read},
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs, io,
     ops::Range,
-    path::Path,
+    path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-const LICENSE_PATH: &str = ".licensesnip";
-
-fn prepend_file(header_text: &[u8], file_path: &Path, skip_shebang_line: bool) -> io::Result<()> {
-    // Create a temporary file
-    let tmp = Temp::new_file()?;
-    let tmp_path = tmp.to_path_buf();
-    // Stop the temp file being automatically deleted when the variable
-    // is dropped, by releasing it.
-    tmp.release();
-    // Open temp file for writing
-    let mut tmp = File::create(&tmp_path)?;
-    // Open source file for reading
-    let mut src = File::open(file_path)?;
-    // Copy file permissions
-    let src_metadata = src.metadata()?;
-    tmp.set_permissions(src_metadata.permissions())?;
-    if skip_shebang_line {
-        let mut shebang_line = String::new();
-        let src = BufReader::new(&src);
-        let mut lines = src.lines();
-        if let Some(line) = lines.next() {
-            shebang_line = line.unwrap();
-        }
-        shebang_line.push('\n');
-        if shebang_line.starts_with("#!") {
-            // Write the shebang line first.
-            tmp.write_all(shebang_line.as_bytes())?;
-            tmp.write_all(header_text)?;
-        } else {
-            tmp.write_all(header_text)?;
-            // Write the first line that we've already read.
-            tmp.write_all(shebang_line.as_bytes())?;
-        }
-        for line in lines {
-            tmp.write_all(line.unwrap().as_bytes())?;
-            tmp.write_all(b"\n")?;
-        }
-    } else {
-        tmp.write_all(header_text)?;
-        // Copy the rest of the source file
-        io::copy(&mut src, &mut tmp)?;
-    }
-    drop(tmp);
-    drop(src);
-    fs::remove_file(file_path)?;
-    fs::copy(&tmp_path, file_path)?;
-    fs::remove_file(&tmp_path)?;
-    Ok(())
-}
-
-fn remove_range_of_chars(omit_range: Range<u32>, file_path: &Path) -> io::Result<()> {
-    // Create a temporary file
-    let tmp = Temp::new_file()?;
-    let tmp_path = tmp.to_path_buf();
-    // Stop the temp file being automatically deleted when the variable
-    // is dropped, by releasing it.
-    tmp.release();
-    // Open temp file for writing
-    let mut tmp = File::create(&tmp_path)?;
-    // Open source file for reading
-    let mut src = File::open(file_path)?;
-    // Copy file permissions
-    let src_metadata = src.metadata()?;
-    tmp.set_permissions(src_metadata.permissions())?;
-
-    let mut i = 0;
+pub const LICENSE_PATH: &str = ".licensesnip";
+
+/// Ending of the sidecar file that holds the header of a file in sidecar mode.
+pub const SIDECAR_SUFFIX: &str = ".license";
+
+/// Gets the path of a file's sidecar, e.g. `logo.png.license` for `logo.png`.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = OsString::from(path.as_os_str());
+    sidecar.push(SIDECAR_SUFFIX);
+    PathBuf::from(sidecar)
+}
+
+/// Gets the path of the file that holds a file's header, which is the file
+/// itself unless it's in sidecar mode.
+pub fn header_file_path(path: &Path, file_type_config: &FileTypeConfig) -> PathBuf {
+    match file_type_config.mode {
+        FileMode::Sidecar => sidecar_path(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Reads a file's sidecar, or gives `None` if it has none.
+fn read_sidecar(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(sidecar_path(path)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Gives the contents of the file holding a header when they were read
+/// already, or reads them otherwise.
+fn read_contents(path: &Path, contents: Option<Vec<u8>>) -> io::Result<Vec<u8>> {
+    match contents {
+        Some(bytes) => Ok(bytes),
+        None => fs::read(path),
+    }
+}
+
+/// Matches any text on a single line, such as a file name or a list of authors.
+const LINE_PATTERN: &str = r"[^\r\n]*";
+/// Matches a year, a year range such as `2019-2022` or a list such as
+/// `2019, 2021-2023`.
+const YEARS_PATTERN: &str = r"[0-9]{4}(?:(?:\s*[-–]\s*|,\s*)[0-9]{4})*";
+/// Same as `YEARS_PATTERN`, but captures the year so it can be preserved.
+const YEAR_PATTERN: &str = r"([0-9]{4}(?:(?:\s*[-–]\s*|,\s*)[0-9]{4})*)";
+
+/// Matches a date written as `YYYY-MM-DD`.
+const DATE_PATTERN: &str = r"[0-9]{4}-[0-9]{2}-[0-9]{2}";
+
+/// Each built-in placeholder, the stand-in used for it while a header pattern
+/// is built, and the pattern that matches any value it can take. The stand-ins
+/// are private use characters so they can't clash with real license text.
+const BUILTIN_VARIABLES: [(&str, &str, &str); 11] = [
+    ("FILENAME", "\u{E000}", LINE_PATTERN),
+    ("FILEPATH", "\u{E001}", LINE_PATTERN),
+    ("DIRNAME", "\u{E002}", LINE_PATTERN),
+    ("STEM", "\u{E003}", LINE_PATTERN),
+    ("EXT", "\u{E004}", LINE_PATTERN),
+    ("DATE", "\u{E005}", DATE_PATTERN),
+    ("YEAR", "\u{E006}", YEAR_PATTERN),
+    ("CREATED_YEAR", "\u{E007}", YEARS_PATTERN),
+    ("MODIFIED_YEAR", "\u{E008}", YEARS_PATTERN),
+    ("YEARS", "\u{E009}", YEARS_PATTERN),
+    ("AUTHORS", "\u{E00A}", LINE_PATTERN),
+];
+
+/// Placeholders whose values come from the file's git history.
+pub const GIT_VARIABLES: [&str; 4] = ["CREATED_YEAR", "MODIFIED_YEAR", "YEARS", "AUTHORS"];
+
+/// Resolves a placeholder that has the same value for every file: an
+/// environment variable or a variable from the config.
+fn resolve_constant_variable(
+    name: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, TemplateErr> {
+    if let Some(env_name) = name.strip_prefix(ENV_PREFIX) {
+        return env::var(env_name).map_err(|_| TemplateErr::UnsetEnvVariable(env_name.to_string()));
+    }
+
+    match variables.get(name) {
+        Some(value) => Ok(value.clone()),
+        None => Err(TemplateErr::UnknownVariable(name.to_string())),
+    }
+}
+
+/// The byte order mark that some editors put at the start of UTF-8 files.
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Gets the line ending a file uses, judging by its first line.
+pub(crate) fn line_ending(file: &[u8]) -> &'static [u8] {
+    match file.iter().position(|&ch| ch == b'\n') {
+        Some(i) if i > 0 && file[i - 1] == b'\r' => b"\r\n",
+        _ => b"\n",
+    }
+}
+
+/// Converts the `\n` line endings of generated text to a file's line ending.
+fn with_line_ending(text: &str, eol: &[u8]) -> Vec<u8> {
+    if eol == b"\n" {
+        return text.as_bytes().to_vec();
+    }
+    text.replace('\n', "\r\n").into_bytes()
+}
+
+/// Replaces a range of a file's contents.
+pub(crate) fn splice(src: &[u8], range: Range<usize>, replacement: &[u8]) -> Vec<u8> {
+    let mut text = Vec::with_capacity(src.len() - range.len() + replacement.len());
+    text.extend_from_slice(&src[..range.start]);
+    text.extend_from_slice(replacement);
+    text.extend_from_slice(&src[range.end..]);
+    text
+}
+
+/// Puts a header at the top of a file's contents, below its preamble.
+fn insert_header(src: &[u8], header_text: &str, file_type_config: &FileTypeConfig) -> Vec<u8> {
+    let eol = line_ending(src);
+    let header_start = preamble_len(src, file_type_config);
+    let preamble = &src[..header_start];
     let mut text = Vec::<u8>::new();
-    let full_text = BufReader::new(&src).bytes();
 
-    for byte in full_text {
-        match byte {
-            Ok(byte) => {
-                if !omit_range.contains(&i) {
-                    text.push(byte)
-                }
-                i += 1;
-            }
-            Err(e) => {
-                println!("{}", e);
-                std::process::exit(exitcode::IOERR);
-            }
-        }
+    if !preamble.is_empty() && !preamble.ends_with(b"\n") && preamble != UTF8_BOM {
+        text.extend_from_slice(eol);
+    }
+    if file_type_config.placement == Placement::InFrontMatter
+        && front_matter(&src[leading_lines_len(src, file_type_config)..]).is_none()
+    {
+        // Put the header in new front matter of its own
+        let fenced = format!("---\n{}\n---\n\n", header_text.trim_end());
+        text.extend_from_slice(&with_line_ending(&fenced, eol));
+    } else {
+        text.extend_from_slice(&with_line_ending(header_text, eol));
     }
 
-    // Write the data to prepend
-    tmp.write_all(text.as_slice())?;
-    // Copy the rest of the source file
-    io::copy(&mut src, &mut tmp)?;
-    drop(src);
-    drop(tmp);
-    fs::remove_file(file_path)?;
-    fs::copy(&tmp_path, file_path)?;
-    fs::remove_file(&tmp_path)?;
-    Ok(())
+    splice(src, header_start..header_start, &text)
 }
 
 pub enum ReadLicenseErr {
     FileReadErr,
+    MissingSpdxId,
+    MissingCopyrightHolder,
+    InvalidSpdxExpression(String, SpdxErr),
 }
 
 pub struct License {
     pub raw_text: String,
+    pub template: Template,
+}
+
+/// Values substituted for the placeholders of a license template.
+#[derive(Debug, Clone)]
+pub struct TemplateVars<'a> {
+    pub file_name: String,
+    pub file_path: String,
+    pub dir_name: String,
+    pub stem: String,
+    pub ext: String,
+    pub date: String,
+    pub year: String,
+    pub created_year: String,
+    pub modified_year: String,
+    pub years: String,
+    pub authors: String,
+    /// Variables declared in the config
+    pub variables: &'a HashMap<String, String>,
+}
+
+impl TemplateVars<'_> {
+    pub fn get(&self, name: &str) -> Result<String, TemplateErr> {
+        let value = match name {
+            "FILENAME" => &self.file_name,
+            "FILEPATH" => &self.file_path,
+            "DIRNAME" => &self.dir_name,
+            "STEM" => &self.stem,
+            "EXT" => &self.ext,
+            "DATE" => &self.date,
+            "YEAR" => &self.year,
+            "CREATED_YEAR" => &self.created_year,
+            "MODIFIED_YEAR" => &self.modified_year,
+            "YEARS" => &self.years,
+            "AUTHORS" => &self.authors,
+            _ => return resolve_constant_variable(name, self.variables),
+        };
+        Ok(value.clone())
+    }
 }
 
 impl License {
-    pub fn get_formatted<Y: std::fmt::Display + Copy>(
-        raw_text: &str,
-        file_name: &str,
-        year: Y,
-    ) -> String {
-        raw_text
-            .replace("%FILENAME%", file_name)
-            .replace("%YEAR%", &year.to_string())
+    pub fn new(raw_text: String) -> Self {
+        let template = Template::parse(&raw_text);
+        Self { raw_text, template }
+    }
+
+    pub fn get_formatted_lines(&self, vars: &TemplateVars) -> Result<Vec<String>, TemplateErr> {
+        let text = self.template.render(|name| vars.get(name))?;
+        Ok(text.split('\n').map(str::to_string).collect())
+    }
+
+    /// Formats the license for no file in particular, such as for an
+    /// annotation that covers many files. Only `%YEAR%`, `%DATE%` and
+    /// placeholders that are the same for every file can be used.
+    pub fn get_shared_lines(
+        &self,
+        year: &str,
+        date: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<Vec<String>, TemplateErr> {
+        let text = self.template.render(|name| match name {
+            "YEAR" => Ok(year.to_string()),
+            "DATE" => Ok(date.to_string()),
+            _ if BUILTIN_VARIABLES
+                .iter()
+                .any(|(builtin, _, _)| *builtin == name) =>
+            {
+                Err(TemplateErr::PerFileVariable(name.to_string()))
+            }
+            _ => resolve_constant_variable(name, variables),
+        })?;
+        Ok(text.split('\n').map(str::to_string).collect())
+    }
+
+    /// Builds a regex that matches the header for any file name, year or
+    /// author, so headers written in earlier years are still recognized.
+    pub fn get_header_pattern(
+        &self,
+        cfg: &FileTypeConfig,
+        variables: &HashMap<String, String>,
+    ) -> Result<String, TemplateErr> {
+        let sentinel_text = self.template.render(|name| {
+            match BUILTIN_VARIABLES
+                .iter()
+                .find(|(builtin, _, _)| *builtin == name)
+            {
+                Some((_, sentinel, _)) => Ok(sentinel.to_string()),
+                None => resolve_constant_variable(name, variables),
+            }
+        })?;
+        let sentinel_lines = sentinel_text
+            .split('\n')
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let mut sentinel_header = License::get_header_text(&sentinel_lines, cfg);
+
+        // A placeholder that renders empty at the end of a line takes the
+        // spaces before it with it when the line is trimmed, so let the
+        // placeholder's pattern match those spaces instead.
+        for (_, sentinel, _) in BUILTIN_VARIABLES
+            .iter()
+            .filter(|(_, _, value_pattern)| *value_pattern == LINE_PATTERN)
+        {
+            let spaced_sentinel = format!(" {}", sentinel);
+            while sentinel_header.contains(&spaced_sentinel) {
+                sentinel_header = sentinel_header.replace(&spaced_sentinel, sentinel);
+            }
+        }
+
+        let pattern = BUILTIN_VARIABLES.iter().fold(
+            regex::escape(&sentinel_header),
+            |pattern, (_, sentinel, value_pattern)| pattern.replace(sentinel, value_pattern),
+        );
+
+        Ok(format!(r"\A{}", pattern.replace('\n', r"\r?\n")))
     }
 
-    pub fn get_formatted_lines<Y: std::fmt::Display + Copy>(
-        lines: &Vec<&str>,
-        file_name: &str,
-        year: Y,
-    ) -> Vec<String> {
-        let mut vec = Vec::<String>::new();
-        for str in lines {
-            vec.push(License::get_formatted(str, file_name, year))
+    /// Checks that every placeholder in the license is either built in, set in
+    /// the environment or declared in the config.
+    pub fn check_variables(&self, variables: &HashMap<String, String>) -> Result<(), TemplateErr> {
+        for name in self.template.variables() {
+            if !BUILTIN_VARIABLES
+                .iter()
+                .any(|(builtin, _, _)| *builtin == name)
+            {
+                resolve_constant_variable(name, variables)?;
+            }
         }
-        vec
+        Ok(())
     }
 
-    pub fn get_lines(&self) -> Vec<&str> {
-        self.raw_text.split('\n').collect::<Vec<&str>>()
+    /// Whether the license uses any placeholder filled in from git history.
+    pub fn uses_git_history(&self) -> bool {
+        GIT_VARIABLES
+            .iter()
+            .any(|name| self.template.uses_variable(name))
     }
 
     pub fn get_header_text(lines: &Vec<String>, cfg: &FileTypeConfig) -> String {
@@ -204,19 +365,33 @@ impl License {
     pub fn check_file(
         ent: &DirEntry,
         file_type_config: &FileTypeConfig,
-        header_text: &str,
+        header_pattern: &Regex,
+        contents: Option<Vec<u8>>,
     ) -> Result<bool, AddToFileErr> {
         let path = ent.path();
-        let file_text = match fs::read_to_string(path) {
+
+        if file_type_config.mode == FileMode::Sidecar {
+            let sidecar = match contents {
+                Some(bytes) => Ok(Some(bytes)),
+                None => read_sidecar(path),
+            };
+            return match sidecar {
+                Ok(sidecar) => Ok(sidecar.is_some_and(|bytes| header_pattern.is_match(&bytes))),
+                Err(_) => Err(AddToFileErr::ReadFileErr),
+            };
+        }
+
+        let f_bytes = match read_contents(path, contents) {
             Ok(s) => s,
             Err(_) => return Err(AddToFileErr::ReadFileErr),
         };
 
-        let h_bytes = header_text.as_bytes();
-        let f_bytes = file_text.as_bytes();
+        if file_type_config.mode == FileMode::Notebook {
+            let cell = find_header_cell(&f_bytes).map_err(AddToFileErr::InvalidNotebook)?;
+            return Ok(cell.is_some_and(|cell| header_pattern.is_match(cell.source.as_bytes())));
+        }
 
-        let matching_header =
-            file_has_matching_header(h_bytes, f_bytes, file_type_config.skip_shebang_line);
+        let matching_header = file_has_matching_header(header_pattern, &f_bytes, file_type_config);
         Ok(matches!(
             matching_header,
             MatchingHeaderResult::MatchingHeaderAt(_)
@@ -227,54 +402,168 @@ impl License {
         ent: &DirEntry,
         file_type_config: &FileTypeConfig,
         header_text: &str,
+        header_pattern: &Regex,
+        contents: Option<Vec<u8>>,
     ) -> Result<AddToFileResult, AddToFileErr> {
-        if !Self::check_file(ent, file_type_config, header_text)? {
-            let path = ent.path();
-            let mut text_to_add = header_text.to_owned();
-            text_to_add.push_str("\n\n");
-
-            // add to top of file
-            match prepend_file(
-                text_to_add.as_bytes(),
-                path,
-                file_type_config.skip_shebang_line,
-            ) {
-                Ok(_) => {}
-                Err(e) => {
-                    println!("{}", e);
-                    return Err(AddToFileErr::WriteFileErr);
+        let path = ent.path();
+
+        if file_type_config.mode == FileMode::Sidecar {
+            let sidecar = match contents {
+                Some(bytes) => Some(bytes),
+                None => match read_sidecar(path) {
+                    Ok(sidecar) => sidecar,
+                    Err(_) => return Err(AddToFileErr::ReadFileErr),
+                },
+            };
+            let text = format!("{}\n", header_text);
+            let result = match sidecar {
+                Some(bytes) if header_pattern.is_match(&bytes) => {
+                    return Ok(AddToFileResult::NoChange);
                 }
+                // The whole sidecar is the header, so there's nothing else to keep
+                Some(_) => write_file(
+                    &sidecar_path(path),
+                    text.as_bytes(),
+                    file_type_config.preserve_mtime,
+                ),
+                None => create_file(&sidecar_path(path), text.as_bytes()),
             };
+            if let Err(e) = result {
+                println!("{}", e);
+                return Err(AddToFileErr::WriteFileErr);
+            }
+            return Ok(AddToFileResult::Added);
+        }
+
+        let mut f_bytes = match read_contents(path, contents) {
+            Ok(s) => s,
+            Err(_) => return Err(AddToFileErr::ReadFileErr),
+        };
 
+        if file_type_config.mode == FileMode::Notebook {
+            let cell = find_header_cell(&f_bytes).map_err(AddToFileErr::InvalidNotebook)?;
+            let text = match cell {
+                Some(cell) if header_pattern.is_match(cell.source.as_bytes()) => {
+                    return Ok(AddToFileResult::NoChange);
+                }
+                // A tagged cell is always a header, so an outdated one is
+                // replaced rather than kept alongside the new one
+                Some(cell) => replace_header_cell(
+                    &f_bytes,
+                    &cell,
+                    header_text,
+                    file_type_config.notebook_cell,
+                ),
+                None => add_header_cell(&f_bytes, header_text, file_type_config.notebook_cell),
+            }
+            .map_err(AddToFileErr::InvalidNotebook)?;
+
+            if let Err(e) = write_file(path, &text, file_type_config.preserve_mtime) {
+                println!("{}", e);
+                return Err(AddToFileErr::WriteFileErr);
+            }
             return Ok(AddToFileResult::Added);
         }
 
-        Ok(AddToFileResult::NoChange)
+        if let MatchingHeaderResult::MatchingHeaderAt(_) =
+            file_has_matching_header(header_pattern, &f_bytes, file_type_config)
+        {
+            return Ok(AddToFileResult::NoChange);
+        }
+
+        // Take out a header that was put below a line that isn't a shebang,
+        // so it can be added back at the top.
+        let misplaced = find_misplaced_header(header_pattern, &f_bytes, file_type_config);
+        if let MatchingHeaderResult::MatchingHeaderAt(header_range) = &misplaced {
+            f_bytes = splice(&f_bytes, header_range.clone(), b"");
+        }
+
+        let mut text_to_add = header_text.to_owned();
+        text_to_add.push_str("\n\n");
+
+        // add to top of file
+        let text = insert_header(&f_bytes, &text_to_add, file_type_config);
+        if let Err(e) = write_file(path, &text, file_type_config.preserve_mtime) {
+            println!("{}", e);
+            return Err(AddToFileErr::WriteFileErr);
+        }
+
+        match misplaced {
+            MatchingHeaderResult::MatchingHeaderAt(_) => Ok(AddToFileResult::Relocated),
+            MatchingHeaderResult::NotMatching => Ok(AddToFileResult::Added),
+        }
     }
 
     pub fn remove_from_file(
         ent: &DirEntry,
         file_type_config: &FileTypeConfig,
-        header_text: &str,
+        header_pattern: &Regex,
+        contents: Option<Vec<u8>>,
     ) -> Result<RemoveFromFileResult, RemoveFromFileErr> {
         let path = ent.path();
-        let file_text = match fs::read_to_string(path) {
+
+        if file_type_config.mode == FileMode::Sidecar {
+            let sidecar = match contents {
+                Some(bytes) => Some(bytes),
+                None => match read_sidecar(path) {
+                    Ok(sidecar) => sidecar,
+                    Err(_) => return Err(RemoveFromFileErr::ReadFileErr),
+                },
+            };
+            if !sidecar.is_some_and(|bytes| header_pattern.is_match(&bytes)) {
+                return Ok(RemoveFromFileResult::NoChange);
+            }
+            return match fs::remove_file(sidecar_path(path)) {
+                Ok(_) => Ok(RemoveFromFileResult::Removed),
+                Err(e) => {
+                    println!("{}", e);
+                    Err(RemoveFromFileErr::WriteFileErr)
+                }
+            };
+        }
+
+        let f_bytes = match read_contents(path, contents) {
             Ok(s) => s,
             Err(_) => return Err(RemoveFromFileErr::ReadFileErr),
         };
 
-        let h_bytes = header_text.as_bytes();
-        let f_bytes = file_text.as_bytes();
+        if file_type_config.mode == FileMode::Notebook {
+            let cell = find_header_cell(&f_bytes).map_err(RemoveFromFileErr::InvalidNotebook)?;
+            let Some(cell) = cell.filter(|cell| header_pattern.is_match(cell.source.as_bytes()))
+            else {
+                return Ok(RemoveFromFileResult::NoChange);
+            };
 
-        let f_match =
-            file_has_matching_header(h_bytes, f_bytes, file_type_config.skip_shebang_line);
+            let text = remove_header_cell(&f_bytes, &cell);
+            return match write_file(path, &text, file_type_config.preserve_mtime) {
+                Ok(_) => Ok(RemoveFromFileResult::Removed),
+                Err(e) => {
+                    println!("{}", e);
+                    Err(RemoveFromFileErr::WriteFileErr)
+                }
+            };
+        }
+
+        let f_match = file_has_matching_header(header_pattern, &f_bytes, file_type_config);
 
-        let MatchingHeaderResult::MatchingHeaderAt(header_range) = f_match else {
+        let MatchingHeaderResult::MatchingHeaderAt(mut header_range) = f_match else {
             return Ok(RemoveFromFileResult::NoChange);
         };
 
+        // Remove front matter that holds nothing but the header
+        if file_type_config.placement == Placement::InFrontMatter {
+            let start = leading_lines_len(&f_bytes, file_type_config);
+            if let Some(fm) = front_matter(&f_bytes[start..]) {
+                if header_range.end == start + fm.closing_start {
+                    let end = start + fm.end;
+                    header_range = start..end + trailing_newlines_len(&f_bytes[end..]);
+                }
+            }
+        }
+
         // remove from top of file
-        match remove_range_of_chars(header_range, path) {
+        let text = splice(&f_bytes, header_range, b"");
+        match write_file(path, &text, file_type_config.preserve_mtime) {
             Ok(_) => Ok(RemoveFromFileResult::Removed),
             Err(e) => {
                 println!("{}", e);
@@ -282,56 +571,459 @@ impl License {
             }
         }
     }
+
+    pub fn update_file(
+        ent: &DirEntry,
+        file_type_config: &FileTypeConfig,
+        header_text: &str,
+        header_pattern: &Regex,
+        contents: Option<Vec<u8>>,
+    ) -> Result<UpdateFileResult, UpdateFileErr> {
+        let path = ent.path();
+
+        if file_type_config.mode == FileMode::Sidecar {
+            let sidecar = match contents {
+                Some(bytes) => Ok(Some(bytes)),
+                None => read_sidecar(path),
+            };
+            let bytes = match sidecar {
+                Ok(Some(bytes)) => bytes,
+                Ok(None) => return Ok(UpdateFileResult::Unrecognized),
+                Err(_) => return Err(UpdateFileErr::ReadFileErr),
+            };
+            let eol = line_ending(&bytes);
+            if bytes.trim_ascii_end() == with_line_ending(header_text, eol) {
+                return Ok(UpdateFileResult::NoChange);
+            }
+
+            let text = with_line_ending(&format!("{}\n", header_text), eol);
+            return match write_file(&sidecar_path(path), &text, file_type_config.preserve_mtime) {
+                Ok(_) => Ok(UpdateFileResult::Updated),
+                Err(e) => {
+                    println!("{}", e);
+                    Err(UpdateFileErr::WriteFileErr)
+                }
+            };
+        }
+
+        let f_bytes = match read_contents(path, contents) {
+            Ok(s) => s,
+            Err(_) => return Err(UpdateFileErr::ReadFileErr),
+        };
+
+        if file_type_config.mode == FileMode::Notebook {
+            let cell = find_header_cell(&f_bytes).map_err(UpdateFileErr::InvalidNotebook)?;
+            let text = match cell {
+                None => return Ok(UpdateFileResult::Unrecognized),
+                Some(cell) if cell.source == header_text => {
+                    return Ok(UpdateFileResult::NoChange);
+                }
+                Some(cell) => replace_header_cell(
+                    &f_bytes,
+                    &cell,
+                    header_text,
+                    file_type_config.notebook_cell,
+                ),
+            }
+            .map_err(UpdateFileErr::InvalidNotebook)?;
+
+            return match write_file(path, &text, file_type_config.preserve_mtime) {
+                Ok(_) => Ok(UpdateFileResult::Updated),
+                Err(e) => {
+                    println!("{}", e);
+                    Err(UpdateFileErr::WriteFileErr)
+                }
+            };
+        }
+
+        let eol = line_ending(&f_bytes);
+        let f_match = file_has_matching_header(header_pattern, &f_bytes, file_type_config);
+
+        let header_range = match f_match {
+            MatchingHeaderResult::MatchingHeaderAt(range) => {
+                let existing = &f_bytes[range.start..range.end];
+                if existing.trim_ascii_end() == with_line_ending(header_text, eol) {
+                    return Ok(UpdateFileResult::NoChange);
+                }
+                range
+            }
+            MatchingHeaderResult::NotMatching => {
+                match find_existing_header(&f_bytes, file_type_config, header_text) {
+                    Some(range) => range,
+                    None => return Ok(UpdateFileResult::Unrecognized),
+                }
+            }
+        };
+
+        let mut text_to_add = header_text.to_owned();
+        text_to_add.push_str("\n\n");
+
+        // replace the old header at the top of file
+        let text = splice(&f_bytes, header_range, &with_line_ending(&text_to_add, eol));
+        match write_file(path, &text, file_type_config.preserve_mtime) {
+            Ok(_) => Ok(UpdateFileResult::Updated),
+            Err(e) => {
+                println!("{}", e);
+                Err(UpdateFileErr::WriteFileErr)
+            }
+        }
+    }
+}
+
+/// A license header read from a file.
+pub struct FileHeader {
+    pub text: Vec<u8>,
+    /// Whether the header matches the current template
+    pub is_current: bool,
+}
+
+/// Reads a file's license header, either one matching the current template or
+/// a license comment block from an older one.
+pub fn read_header(
+    path: &Path,
+    file_type_config: &FileTypeConfig,
+    header_text: &str,
+    header_pattern: &Regex,
+    contents: Option<Vec<u8>>,
+) -> io::Result<Option<FileHeader>> {
+    let header = |text: &[u8]| {
+        Some(FileHeader {
+            is_current: header_pattern.is_match(text),
+            text: text.to_vec(),
+        })
+    };
+
+    match file_type_config.mode {
+        FileMode::Sidecar => {
+            let sidecar = match contents {
+                Some(bytes) => Some(bytes),
+                None => read_sidecar(path)?,
+            };
+            Ok(sidecar.and_then(|bytes| header(&bytes)))
+        }
+        FileMode::Notebook => {
+            let cell = find_header_cell(&read_contents(path, contents)?)
+                .ok()
+                .flatten();
+            Ok(cell.and_then(|cell| header(cell.source.as_bytes())))
+        }
+        FileMode::Inline => {
+            let f_bytes = read_contents(path, contents)?;
+            let range = match file_has_matching_header(header_pattern, &f_bytes, file_type_config) {
+                MatchingHeaderResult::MatchingHeaderAt(range) => range,
+                MatchingHeaderResult::NotMatching => {
+                    match find_existing_header(&f_bytes, file_type_config, header_text) {
+                        Some(range) => range,
+                        None => return Ok(None),
+                    }
+                }
+            };
+            Ok(header(&f_bytes[range]))
+        }
+    }
+}
+
+/// Reads the year written in a file's existing header, either from a header
+/// matching the current template or from an older license comment block.
+/// `f_bytes` are the contents of the file holding the header, which is the
+/// sidecar for files in sidecar mode.
+pub fn read_header_year(
+    f_bytes: &[u8],
+    file_type_config: &FileTypeConfig,
+    header_text: &str,
+    header_pattern: &Regex,
+) -> Option<String> {
+    if file_type_config.mode == FileMode::Notebook {
+        let source = find_header_cell(f_bytes).ok()??.source;
+        let year_pattern = Regex::new(YEAR_PATTERN).expect("year pattern should be a valid regex");
+        let captures = year_pattern.captures(source.as_bytes())?;
+        return Some(String::from_utf8_lossy(captures.get(1)?.as_bytes()).into_owned());
+    }
+
+    let header_start = preamble_len(f_bytes, file_type_config);
+
+    if let Some(captures) = header_pattern.captures(&f_bytes[header_start..]) {
+        if let Some(year) = captures.get(1) {
+            return Some(String::from_utf8_lossy(year.as_bytes()).into_owned());
+        }
+    }
+
+    let header_range = find_existing_header(f_bytes, file_type_config, header_text)?;
+    let year_pattern = Regex::new(YEAR_PATTERN).expect("year pattern should be a valid regex");
+    let captures = year_pattern.captures(&f_bytes[header_range.start..header_range.end])?;
+    Some(String::from_utf8_lossy(captures.get(1)?.as_bytes()).into_owned())
 }
 
 #[derive(Debug)]
 enum MatchingHeaderResult {
-    MatchingHeaderAt(Range<u32>),
+    MatchingHeaderAt(Range<usize>),
     NotMatching,
 }
 
+fn shebang_line_len(file: &[u8], cfg: &FileTypeConfig) -> usize {
+    if !cfg.skip_shebang_line {
+        return 0;
+    }
+
+    let line_len = match file.iter().position(|&ch| ch == b'\n') {
+        Some(i) => i + 1,
+        None => file.len(),
+    };
+    let is_shebang = match std::str::from_utf8(&file[..line_len]) {
+        Ok(line) => cfg.shebang.is_match(line),
+        Err(_) => false,
+    };
+
+    if is_shebang {
+        line_len
+    } else {
+        0
+    }
+}
+
+/// Finds a header that an older version placed below a first line that
+/// starts with `#!` but isn't a shebang, such as Rust's `#![...]`.
+fn find_misplaced_header(
+    header_pattern: &Regex,
+    file: &[u8],
+    cfg: &FileTypeConfig,
+) -> MatchingHeaderResult {
+    if !cfg.skip_shebang_line || !file.starts_with(b"#!") || shebang_line_len(file, cfg) > 0 {
+        return MatchingHeaderResult::NotMatching;
+    }
+
+    let header_start = match file.iter().position(|&ch| ch == b'\n') {
+        Some(i) => i + 1,
+        None => return MatchingHeaderResult::NotMatching,
+    };
+
+    let Some(header_match) = header_pattern.find(&file[header_start..]) else {
+        return MatchingHeaderResult::NotMatching;
+    };
+
+    let mut header_end = header_start + header_match.end();
+    header_end += trailing_newlines_len(&file[header_end..]);
+
+    MatchingHeaderResult::MatchingHeaderAt(header_start..header_end)
+}
+
+/// Gets the length of the lines at the start of a file that stay above the
+/// header: the BOM, the shebang line, then any lines matching the preamble
+/// patterns.
+fn leading_lines_len(file: &[u8], cfg: &FileTypeConfig) -> usize {
+    let mut len = if file.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    };
+    len += shebang_line_len(&file[len..], cfg);
+
+    for line in file[len..].split_inclusive(|&ch| ch == b'\n') {
+        let is_preamble = match std::str::from_utf8(line) {
+            Ok(text) => cfg.preamble.is_match(text.trim_end_matches(['\r', '\n'])),
+            Err(_) => false,
+        };
+        if !is_preamble {
+            break;
+        }
+        len += line.len();
+    }
+
+    len
+}
+
+/// Gets the length of everything in a file above the header, which is the
+/// leading lines, then the front matter or its opening `---` depending on the
+/// placement.
+fn preamble_len(file: &[u8], cfg: &FileTypeConfig) -> usize {
+    let len = leading_lines_len(file, cfg);
+    let front_matter = front_matter(&file[len..]);
+
+    len + match (cfg.placement, front_matter) {
+        (Placement::Top, _) | (_, None) => 0,
+        // Blank lines after the front matter stay above the header too
+        (Placement::AfterFrontMatter, Some(fm)) => {
+            fm.end + trailing_newlines_len(&file[(len + fm.end)..])
+        }
+        (Placement::InFrontMatter, Some(fm)) => fm.opening_end,
+    }
+}
+
+/// The offsets of a `---` front matter block.
+struct FrontMatter {
+    /// End of the opening `---` line
+    opening_end: usize,
+    /// Start of the closing `---` line
+    closing_start: usize,
+    /// End of the closing `---` line
+    end: usize,
+}
+
+/// Finds a front matter block at the very start of `file`.
+fn front_matter(file: &[u8]) -> Option<FrontMatter> {
+    let is_fence = |line: &[u8]| line.trim_ascii_end() == b"---";
+
+    let mut lines = file.split_inclusive(|&ch| ch == b'\n');
+    let opening = lines.next()?;
+    if !is_fence(opening) {
+        return None;
+    }
+
+    let opening_end = opening.len();
+    let mut closing_start = opening_end;
+    for line in lines {
+        if is_fence(line) {
+            return Some(FrontMatter {
+                opening_end,
+                closing_start,
+                end: closing_start + line.len(),
+            });
+        }
+        closing_start += line.len();
+    }
+
+    None
+}
+
 fn file_has_matching_header(
-    header: &[u8],
+    header_pattern: &Regex,
     mut file: &[u8],
-    skip_shebang_line: bool,
+    file_type_config: &FileTypeConfig,
 ) -> MatchingHeaderResult {
-    let mut header_start: u32 = 0;
-
-    if skip_shebang_line && file.len() >= 2 && &file[0..2] == b"#!" {
-        // Skip the shebang line
-        while !file.is_empty() {
-            header_start += 1;
-            let ch = file[0];
-            file = &file[1..];
-            if ch == b'\n' {
+    // Skip the shebang line and preamble
+    let header_start = preamble_len(file, file_type_config);
+    file = &file[header_start..];
+
+    let Some(header_match) = header_pattern.find(file) else {
+        return MatchingHeaderResult::NotMatching;
+    };
+
+    let mut header_end: usize = header_start + header_match.end();
+    file = &file[header_match.end()..];
+
+    header_end += trailing_newlines_len(file);
+
+    MatchingHeaderResult::MatchingHeaderAt(header_start..header_end)
+}
+
+fn trailing_newlines_len(file: &[u8]) -> usize {
+    file.iter()
+        .take_while(|&&ch| ch == b'\n' || ch == b'\r')
+        .count()
+}
+
+/// A line that marks a comment block as a license header when the block doesn't
+/// share any lines with the current template: a copyright notice or SPDX tag
+/// with a year.
+const COPYRIGHT_LINE_PATTERN: &str =
+    r"(?i)(copyright|©|spdx-).*\b[0-9]{4}\b|\b[0-9]{4}\b.*(copyright|©|spdx-)";
+
+/// `COPYRIGHT_LINE_PATTERN`, compiled once as it's matched against every file.
+fn copyright_line() -> &'static Regex {
+    static COPYRIGHT_LINE: OnceLock<Regex> = OnceLock::new();
+    COPYRIGHT_LINE.get_or_init(|| {
+        Regex::new(COPYRIGHT_LINE_PATTERN).expect("copyright line pattern should be a valid regex")
+    })
+}
+
+/// Starts of doc comments, which document code rather than hold a header.
+const DOC_COMMENT_PREFIXES: [&str; 4] = ["///", "//!", "/**", "/*!"];
+
+/// Whether a line starts a doc comment that the comment prefix of the file
+/// type doesn't already account for.
+fn is_doc_comment(line: &str, prefix: &str) -> bool {
+    DOC_COMMENT_PREFIXES
+        .iter()
+        .any(|doc| line.starts_with(doc) && !prefix.starts_with(doc))
+}
+
+/// Finds the comment block at the top of a file that holds a license header,
+/// even if it was rendered from an older template or year.
+fn find_existing_header(
+    file: &[u8],
+    cfg: &FileTypeConfig,
+    header_text: &str,
+) -> Option<Range<usize>> {
+    let header_start = preamble_len(file, cfg);
+    let line_prefix = cfg.before_line.trim();
+    let block_start = cfg.before_block.trim();
+    let block_end = cfg.after_block.trim();
+
+    let mut lines = file[header_start..].split_inclusive(|&ch| ch == b'\n');
+    let mut block_lines = Vec::<String>::new();
+    let trim = |line: &[u8]| String::from_utf8_lossy(line).trim().to_string();
+    let mut header_end = header_start;
+
+    if !block_start.is_empty() {
+        let first = lines.next()?;
+        if trim(first) != block_start {
+            return None;
+        }
+        header_end += first.len();
+
+        let mut closed = block_end.is_empty();
+        for line in lines {
+            let trimmed = trim(line);
+            if block_end.is_empty() {
+                if line_prefix.is_empty()
+                    || !trimmed.starts_with(line_prefix)
+                    || is_doc_comment(&trimmed, line_prefix)
+                {
+                    break;
+                }
+            } else if trimmed == block_end {
+                header_end += line.len();
+                closed = true;
+                break;
+            }
+            header_end += line.len();
+            block_lines.push(trimmed);
+        }
+
+        if !closed {
+            return None;
+        }
+    } else {
+        if line_prefix.is_empty() {
+            return None;
+        }
+        for line in lines {
+            let trimmed = trim(line);
+            if !trimmed.starts_with(line_prefix) || is_doc_comment(&trimmed, line_prefix) {
                 break;
             }
+            header_end += line.len();
+            block_lines.push(trimmed);
         }
     }
 
-    let mut header_end: u32 = header_start;
-    if file.len() < header.len() || header != &file[0..header.len()] {
-        return MatchingHeaderResult::NotMatching;
+    if block_lines.is_empty() {
+        return None;
     }
 
-    header_end += header.len() as u32;
-    file = &file[header.len()..];
+    let is_content_line = |line: &&str| line.chars().any(char::is_alphanumeric);
+    let shares_a_line = header_text
+        .lines()
+        .map(str::trim)
+        .filter(is_content_line)
+        .any(|line| block_lines.iter().any(|block_line| block_line == line));
+    let has_copyright_line = block_lines
+        .iter()
+        .any(|line| copyright_line().is_match(line.as_bytes()));
 
-    while !file.is_empty() {
-        let ch = file[0];
-        if ch == b'\n' || ch == b'\r' {
-            header_end += 1;
-            file = &file[1..];
-            continue;
-        }
-        break;
+    if !shares_a_line && !has_copyright_line {
+        return None;
     }
 
-    MatchingHeaderResult::MatchingHeaderAt(header_start..header_end)
+    header_end += trailing_newlines_len(&file[header_end..]);
+
+    Some(header_start..header_end)
 }
 
 pub enum AddToFileResult {
     Added,
+    /// The header was moved above a line that was mistaken for a shebang
+    Relocated,
     NoChange,
 }
 
@@ -340,25 +1032,432 @@ pub enum RemoveFromFileResult {
     NoChange,
 }
 
+pub enum UpdateFileResult {
+    Updated,
+    NoChange,
+    Unrecognized,
+}
+
 #[derive(Debug)]
 pub enum AddToFileErr {
     ReadFileErr,
     WriteFileErr,
+    InvalidNotebook(NotebookErr),
 }
 
 #[derive(Debug)]
 pub enum RemoveFromFileErr {
     ReadFileErr,
     WriteFileErr,
+    InvalidNotebook(NotebookErr),
+}
+
+#[derive(Debug)]
+pub enum UpdateFileErr {
+    ReadFileErr,
+    WriteFileErr,
+    InvalidNotebook(NotebookErr),
 }
 
-pub fn read_license() -> Result<License, ReadLicenseErr> {
-    let read_result = fs::read_to_string(LICENSE_PATH);
+/// Reads the `.licensesnip` file in a directory.
+pub fn read_license(dir: &Path) -> Result<License, ReadLicenseErr> {
+    let read_result = fs::read_to_string(dir.join(LICENSE_PATH));
 
     match read_result {
-        Ok(str) => Ok(License {
-            raw_text: str.trim().to_string(),
-        }),
+        Ok(str) => Ok(License::new(str.trim().to_string())),
         Err(_) => Err(ReadLicenseErr::FileReadErr),
     }
 }
+
+/// Builds a header of SPDX tags from the `spdx_id` and `copyright_holder` in
+/// the config. The copyright holder may use placeholders.
+pub fn spdx_license(config: &Config) -> Result<License, ReadLicenseErr> {
+    let spdx_id = match &config.spdx_id {
+        Some(id) => id.trim(),
+        None => return Err(ReadLicenseErr::MissingSpdxId),
+    };
+    let copyright_holder = match &config.copyright_holder {
+        Some(holder) => holder.trim(),
+        None => return Err(ReadLicenseErr::MissingCopyrightHolder),
+    };
+
+    if let Err(e) = validate_expression(spdx_id) {
+        return Err(ReadLicenseErr::InvalidSpdxExpression(
+            spdx_id.to_string(),
+            e,
+        ));
+    }
+
+    Ok(License::new(format!(
+        "SPDX-FileCopyrightText: %YEAR% {}\nSPDX-License-Identifier: {}",
+        copyright_holder,
+        spdx_id.replace('%', "%%")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_comments() -> FileTypeConfig {
+        FileTypeConfig {
+            before_line: String::from("// "),
+            ..FileTypeConfig::default()
+        }
+    }
+
+    const HEADER: &str = "// Copyright 2026 Acme\n// MIT License";
+
+    fn existing_header(file: &str) -> Option<&str> {
+        find_existing_header(file.as_bytes(), &line_comments(), HEADER).map(|range| &file[range])
+    }
+
+    fn header_pattern(template: &str, cfg: &FileTypeConfig) -> Regex {
+        let pattern = License::new(template.to_string())
+            .get_header_pattern(cfg, &HashMap::new())
+            .unwrap();
+        Regex::new(&pattern).unwrap()
+    }
+
+    fn matching_header(pattern: &Regex, file: &[u8], cfg: &FileTypeConfig) -> Option<Range<usize>> {
+        match file_has_matching_header(pattern, file, cfg) {
+            MatchingHeaderResult::MatchingHeaderAt(range) => Some(range),
+            MatchingHeaderResult::NotMatching => None,
+        }
+    }
+
+    #[test]
+    fn header_pattern_matches_earlier_years() {
+        let cfg = line_comments();
+        let pattern = header_pattern("Copyright %YEAR% Acme\nMIT License", &cfg);
+
+        for year in [
+            "2019",
+            "2019-2021",
+            "2019 - 2021",
+            "2019, 2021-2023",
+            "2020–2022",
+        ] {
+            let file = format!(
+                "// Copyright {} Acme\n// MIT License\n\nfn main() {{}}\n",
+                year
+            );
+            let captures = pattern.captures(file.as_bytes()).expect(year);
+            assert_eq!(&captures[1], year.as_bytes());
+        }
+        assert!(!pattern.is_match(b"// Copyright Acme\n// MIT License\n"));
+        assert!(!pattern.is_match(b"// Copyright 2019 Other\n// MIT License\n"));
+    }
+
+    #[test]
+    fn header_pattern_matches_any_file_name() {
+        let cfg = line_comments();
+        let pattern = header_pattern("%FILENAME% is part of %DIRNAME%", &cfg);
+
+        assert!(pattern.is_match(b"// main.rs is part of src/bin\n"));
+        assert!(pattern.is_match(b"// build.rs is part of \n"));
+        assert!(!pattern.is_match(b"// main.rs belongs to src\n"));
+    }
+
+    #[test]
+    fn header_pattern_matches_placeholder_that_rendered_empty() {
+        let cfg = line_comments();
+        let pattern = header_pattern("Copyright %YEAR% %AUTHORS%\nMIT License", &cfg);
+
+        // Trimming the line took the space before the empty author list
+        assert!(pattern.is_match(b"// Copyright 2020\n// MIT License\n"));
+        assert!(pattern.is_match(b"// Copyright 2020 Jane Doe, Max\n// MIT License\n"));
+    }
+
+    #[test]
+    fn header_pattern_escapes_license_text() {
+        let cfg = line_comments();
+        let pattern = header_pattern("(c) %YEAR% Acme. [MIT]", &cfg);
+
+        assert!(pattern.is_match(b"// (c) 2020 Acme. [MIT]\n"));
+        assert!(!pattern.is_match(b"// c 2020 AcmeX MIT\n"));
+    }
+
+    #[test]
+    fn header_pattern_matches_block_comments() {
+        let cfg = FileTypeConfig {
+            before_block: String::from("/*"),
+            before_line: String::from("  "),
+            after_block: String::from("*/"),
+            ..FileTypeConfig::default()
+        };
+        let pattern = header_pattern("Copyright %YEAR% Acme", &cfg);
+
+        assert!(pattern.is_match(b"/*\n  Copyright 2001 Acme\n*/\n"));
+        assert!(pattern.is_match(b"/*\r\n  Copyright 2001 Acme\r\n*/\r\n"));
+    }
+
+    #[test]
+    fn round_trips_crlf_and_bom() {
+        let cfg = line_comments();
+        let license = License::new(String::from("Copyright %YEAR% Acme\nMIT License"));
+        let pattern = header_pattern(&license.raw_text, &cfg);
+        let lines = vec![
+            String::from("Copyright 2024 Acme"),
+            String::from("MIT License"),
+        ];
+        let header_text = format!("{}\n\n", License::get_header_text(&lines, &cfg));
+
+        for original in [
+            &b"fn main() {}\n"[..],
+            b"fn main() {}\r\n",
+            b"\xEF\xBB\xBFfn main() {}\r\n",
+            b"#!/usr/bin/env run\r\nfn main() {}\r\n",
+            b"",
+        ] {
+            let with_header = insert_header(original, &header_text, &cfg);
+            let eol: &[u8] = if original.ends_with(b"\r\n") {
+                b"\r\n"
+            } else {
+                b"\n"
+            };
+            let header_line = [&b"// Copyright 2024 Acme"[..], eol].concat();
+            assert!(
+                with_header
+                    .windows(header_line.len())
+                    .any(|window| window == header_line),
+                "{:?}",
+                String::from_utf8_lossy(&with_header)
+            );
+
+            let range = matching_header(&pattern, &with_header, &cfg).unwrap();
+            assert_eq!(splice(&with_header, range, b""), original);
+        }
+    }
+
+    #[test]
+    fn keeps_bom_first() {
+        let cfg = line_comments();
+        let with_header = insert_header(b"\xEF\xBB\xBFx\n", "// Copyright 2024 Acme\n\n", &cfg);
+        assert_eq!(with_header, b"\xEF\xBB\xBF// Copyright 2024 Acme\n\nx\n");
+    }
+
+    #[test]
+    fn finds_header_from_an_earlier_year() {
+        let file = "// Copyright 2019 Acme\n// Apache License 2.0\n\nfn main() {}\n";
+        assert_eq!(
+            existing_header(file),
+            Some("// Copyright 2019 Acme\n// Apache License 2.0\n\n")
+        );
+    }
+
+    #[test]
+    fn finds_header_sharing_a_line_with_the_template() {
+        let file = "// Old Holder Inc.\n// MIT License\n\nfn main() {}\n";
+        assert_eq!(
+            existing_header(file),
+            Some("// Old Holder Inc.\n// MIT License\n\n")
+        );
+    }
+
+    #[test]
+    fn finds_spdx_header() {
+        let file =
+            "// SPDX-FileCopyrightText: 2020 Acme\n// SPDX-License-Identifier: MIT\nfn main() {}\n";
+        assert!(existing_header(file).is_some());
+    }
+
+    #[test]
+    fn ignores_doc_comments() {
+        assert_eq!(
+            existing_header("/// Adds two numbers, see the license below\nfn add() {}\n"),
+            None
+        );
+        assert_eq!(
+            existing_header("//! Crate docs, see LICENSE file\n//! Copyright 2020 Acme\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn stops_header_before_doc_comment() {
+        let file = "// Copyright 2019 Acme\n//! Crate docs\n";
+        assert_eq!(existing_header(file), Some("// Copyright 2019 Acme\n"));
+    }
+
+    #[test]
+    fn ignores_block_doc_comment() {
+        let cfg = FileTypeConfig {
+            before_block: String::from("/*"),
+            before_line: String::from("  "),
+            after_block: String::from("*/"),
+            ..FileTypeConfig::default()
+        };
+        let file = "/**\n  Copyright 2019 Acme\n*/\nfn main() {}\n";
+        assert_eq!(find_existing_header(file.as_bytes(), &cfg, HEADER), None);
+    }
+
+    #[test]
+    fn ignores_comment_with_only_a_keyword() {
+        assert_eq!(
+            existing_header("// Checks the license of each dependency\nfn check() {}\n"),
+            None
+        );
+        assert_eq!(
+            existing_header("// Copyright notices are parsed below\nfn parse() {}\n"),
+            None
+        );
+    }
+
+    /// Gets the walk entry of a file, as the file functions take one.
+    fn entry(path: &Path) -> DirEntry {
+        ignore::WalkBuilder::new(path)
+            .build()
+            .next()
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn tells_shebangs_from_inner_attributes() {
+        let cfg = line_comments();
+        assert_eq!(shebang_line_len(b"#!/bin/sh\necho\n", &cfg), 10);
+        assert_eq!(shebang_line_len(b"#! /usr/bin/env python3\n", &cfg), 24);
+        assert_eq!(shebang_line_len(b"#![no_std]\nfn main() {}\n", &cfg), 0);
+        assert_eq!(
+            shebang_line_len(b"#![allow(dead_code)]\nfn main() {}\n", &cfg),
+            0
+        );
+
+        let cfg = FileTypeConfig {
+            skip_shebang_line: false,
+            ..line_comments()
+        };
+        assert_eq!(shebang_line_len(b"#!/bin/sh\necho\n", &cfg), 0);
+    }
+
+    #[test]
+    fn puts_headers_above_inner_attributes() {
+        let cfg = line_comments();
+        let header = "// Copyright 2026 Acme\n// MIT License\n\n";
+        let file = insert_header(b"#![no_std]\nfn main() {}\n", header, &cfg);
+        assert_eq!(
+            String::from_utf8(file).unwrap(),
+            format!("{}#![no_std]\nfn main() {{}}\n", header)
+        );
+
+        let file = insert_header(b"#!/bin/sh\necho\n", header, &cfg);
+        assert_eq!(
+            String::from_utf8(file).unwrap(),
+            format!("#!/bin/sh\n{}echo\n", header)
+        );
+    }
+
+    #[test]
+    fn finds_headers_misplaced_below_inner_attributes() {
+        let cfg = line_comments();
+        let pattern = header_pattern("Copyright %YEAR% Acme\nMIT License", &cfg);
+        let file = b"#![no_std]\n// Copyright 2026 Acme\n// MIT License\n\nfn main() {}\n";
+
+        let MatchingHeaderResult::MatchingHeaderAt(range) =
+            find_misplaced_header(&pattern, file, &cfg)
+        else {
+            panic!("the header should be found below #![no_std]");
+        };
+        assert_eq!(splice(file, range, b""), b"#![no_std]\nfn main() {}\n");
+        assert!(matching_header(&pattern, file, &cfg).is_none());
+
+        // Nothing is misplaced below a real shebang
+        let file = b"#!/bin/sh\n// Copyright 2026 Acme\n// MIT License\n\necho\n";
+        assert!(matches!(
+            find_misplaced_header(&pattern, file, &cfg),
+            MatchingHeaderResult::NotMatching
+        ));
+    }
+
+    #[test]
+    fn moves_headers_misplaced_below_inner_attributes() {
+        let cfg = line_comments();
+        let pattern = header_pattern("Copyright %YEAR% Acme\nMIT License", &cfg);
+        let header = "// Copyright 2026 Acme\n// MIT License";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        fs::write(&path, format!("#![no_std]\n{}\n\nfn main() {{}}\n", header)).unwrap();
+
+        let result = License::add_to_file(&entry(&path), &cfg, header, &pattern, None);
+        assert!(matches!(result, Ok(AddToFileResult::Relocated)));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n\n#![no_std]\nfn main() {{}}\n", header)
+        );
+
+        let result = License::add_to_file(&entry(&path), &cfg, header, &pattern, None);
+        assert!(matches!(result, Ok(AddToFileResult::NoChange)));
+    }
+
+    fn placed(placement: Placement) -> FileTypeConfig {
+        FileTypeConfig {
+            before_line: String::from("# "),
+            placement,
+            ..FileTypeConfig::default()
+        }
+    }
+
+    const FRONT_MATTER: &str = "---\ntitle: Hello\n---\n\n# Hello\n";
+
+    /// Adds a header to a file with the given placement, checks it's found
+    /// again, removes it and gives the file as it was with the header.
+    fn add_and_remove(file: &str, placement: Placement) -> String {
+        let cfg = placed(placement);
+        let pattern = header_pattern("Copyright %YEAR% Acme", &cfg);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.md");
+        fs::write(&path, file).unwrap();
+
+        let header = "# Copyright 2026 Acme";
+        let result = License::add_to_file(&entry(&path), &cfg, header, &pattern, None);
+        assert!(matches!(result, Ok(AddToFileResult::Added)));
+        let with_header = fs::read_to_string(&path).unwrap();
+        let result = License::check_file(&entry(&path), &cfg, &pattern, None);
+        assert!(matches!(result, Ok(true)), "{}", with_header);
+        let result = License::add_to_file(&entry(&path), &cfg, header, &pattern, None);
+        assert!(matches!(result, Ok(AddToFileResult::NoChange)));
+
+        let result = License::remove_from_file(&entry(&path), &cfg, &pattern, None);
+        assert!(matches!(result, Ok(RemoveFromFileResult::Removed)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), file);
+        with_header
+    }
+
+    #[test]
+    fn places_headers_above_front_matter() {
+        assert_eq!(
+            add_and_remove(FRONT_MATTER, Placement::Top),
+            format!("# Copyright 2026 Acme\n\n{}", FRONT_MATTER)
+        );
+    }
+
+    #[test]
+    fn places_headers_after_front_matter() {
+        assert_eq!(
+            add_and_remove(FRONT_MATTER, Placement::AfterFrontMatter),
+            "---\ntitle: Hello\n---\n\n# Copyright 2026 Acme\n\n# Hello\n"
+        );
+        // Without front matter the header goes at the top
+        assert_eq!(
+            add_and_remove("# Hello\n", Placement::AfterFrontMatter),
+            "# Copyright 2026 Acme\n\n# Hello\n"
+        );
+    }
+
+    #[test]
+    fn places_headers_in_front_matter() {
+        assert_eq!(
+            add_and_remove(FRONT_MATTER, Placement::InFrontMatter),
+            "---\n# Copyright 2026 Acme\n\ntitle: Hello\n---\n\n# Hello\n"
+        );
+    }
+
+    #[test]
+    fn creates_front_matter_for_headers() {
+        assert_eq!(
+            add_and_remove("# Hello\n", Placement::InFrontMatter),
+            "---\n# Copyright 2026 Acme\n---\n\n# Hello\n"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+// mod.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Placeholder for the copyright holder in the bundled templates. It's filled
+/// in by `licensesnip init`, so it never reaches a `.licensesnip` file.
+pub const HOLDER_PLACEHOLDER: &str = "%HOLDER%";
+
+/// License header templates bundled into the binary, keyed by SPDX identifier.
+pub const BUNDLED_LICENSES: [(&str, &str); 17] = [
+    ("MIT", include_str!("MIT.txt")),
+    ("Apache-2.0", include_str!("Apache-2.0.txt")),
+    ("BSD-2-Clause", include_str!("BSD-2-Clause.txt")),
+    ("BSD-3-Clause", include_str!("BSD-3-Clause.txt")),
+    ("GPL-2.0-only", include_str!("GPL-2.0-only.txt")),
+    ("GPL-2.0-or-later", include_str!("GPL-2.0-or-later.txt")),
+    ("GPL-3.0-only", include_str!("GPL-3.0-only.txt")),
+    ("GPL-3.0-or-later", include_str!("GPL-3.0-or-later.txt")),
+    ("LGPL-2.1-only", include_str!("LGPL-2.1-only.txt")),
+    ("LGPL-2.1-or-later", include_str!("LGPL-2.1-or-later.txt")),
+    ("LGPL-3.0-only", include_str!("LGPL-3.0-only.txt")),
+    ("LGPL-3.0-or-later", include_str!("LGPL-3.0-or-later.txt")),
+    ("AGPL-3.0-only", include_str!("AGPL-3.0-only.txt")),
+    ("AGPL-3.0-or-later", include_str!("AGPL-3.0-or-later.txt")),
+    ("MPL-2.0", include_str!("MPL-2.0.txt")),
+    ("ISC", include_str!("ISC.txt")),
+    ("Unlicense", include_str!("Unlicense.txt")),
+];
+
+/// Looks up a bundled license by its SPDX identifier, ignoring case. Returns
+/// the identifier as it's spelled by SPDX along with the template.
+pub fn bundled_license(id: &str) -> Option<(&'static str, &'static str)> {
+    BUNDLED_LICENSES
+        .iter()
+        .find(|(bundled_id, _)| bundled_id.eq_ignore_ascii_case(id))
+        .copied()
+}
+
+/// Fills in the copyright holder, and the year if one is given, of a bundled
+/// template. Without a year, `%YEAR%` is kept so it's filled in per file.
+pub fn fill_in_template(template: &str, holder: &str, year: Option<i32>) -> String {
+    // The year goes in first, so that a `%YEAR%` in the holder is kept
+    let text = match year {
+        Some(year) => template.replace("%YEAR%", &year.to_string()),
+        None => template.to_string(),
+    };
+
+    // The holder is written into a template, so escape any `%` in it
+    text.replace(HOLDER_PLACEHOLDER, &holder.replace('%', "%%"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::Template;
+
+    #[test]
+    fn looks_up_licenses_ignoring_case() {
+        assert_eq!(bundled_license("apache-2.0").unwrap().0, "Apache-2.0");
+        assert!(bundled_license("Apache-3.0").is_none());
+    }
+
+    #[test]
+    fn fills_in_holder_and_year() {
+        let template = "Copyright (c) %YEAR% %HOLDER%";
+        assert_eq!(
+            fill_in_template(template, "Acme", Some(2024)),
+            "Copyright (c) 2024 Acme"
+        );
+        assert_eq!(
+            fill_in_template(template, "Acme", None),
+            "Copyright (c) %YEAR% Acme"
+        );
+    }
+
+    #[test]
+    fn escapes_percent_in_holder() {
+        let template = "Copyright (c) %YEAR% %HOLDER%";
+        let render = |text: &str| {
+            Template::parse(text)
+                .render(|name| Ok(format!("<{}>", name)))
+                .unwrap()
+        };
+
+        let text = fill_in_template(template, "100% Acme %YEAR%", Some(2024));
+        assert_eq!(text, "Copyright (c) 2024 100%% Acme %%YEAR%%");
+        assert_eq!(render(&text), "Copyright (c) 2024 100% Acme %YEAR%");
+
+        let text = fill_in_template(template, "100% Acme %YEAR%", None);
+        assert_eq!(render(&text), "Copyright (c) <YEAR> 100% Acme %YEAR%");
+    }
+
+    #[test]
+    fn fills_in_every_bundled_template() {
+        for (id, template) in BUNDLED_LICENSES {
+            let text = fill_in_template(template, "Acme", Some(2024));
+            assert!(!text.contains(HOLDER_PLACEHOLDER), "{}", id);
+            assert_eq!(Template::parse(&text).variables().count(), 0, "{}", id);
+        }
+    }
+}
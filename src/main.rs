@@ -22,23 +22,42 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub mod cache;
 pub mod config;
 pub mod frontend;
+pub mod git;
+pub mod languages;
 pub mod license;
+pub mod licenses;
+pub mod notebook;
+pub mod reuse;
+pub mod sbom;
+pub mod spdx;
+pub mod template;
+pub mod write;
+pub mod year;
 
 mod commands;
 use clap::Parser;
 
-use commands::{Cli, Commands};
+use commands::{Cli, Commands, GlobalOptions};
 
 fn main() {
     let args = Cli::parse();
+    let options = GlobalOptions {
+        year: args.year,
+        jobs: args.jobs,
+    };
 
     if let Some(command) = args.command {
         match command {
             Commands::Config { .. } => commands::config::execute(command),
-            Commands::Remove { .. } => commands::remove::execute(command),
-            Commands::Check { .. } => commands::check::execute(command),
+            Commands::Init { .. } => commands::init::execute(command, options),
+            Commands::Remove { .. } => commands::remove::execute(command, options),
+            Commands::Check { .. } => commands::check::execute(command, options),
+            Commands::Update { .. } => commands::update::execute(command, options),
+            Commands::Reuse { .. } => commands::reuse::execute(command, options),
+            Commands::Export { .. } => commands::export::execute(command, options),
         };
     } else {
         commands::default::execute(args);
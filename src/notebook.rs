@@ -0,0 +1,465 @@
+// notebook.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use serde_json::{json, ser::PrettyFormatter, Serializer, Value};
+
+use crate::config::NotebookCell;
+use crate::license::{line_ending, splice, UTF8_BOM};
+
+/// Tag of the cell that holds the header.
+pub const HEADER_CELL_TAG: &str = "licensesnip";
+
+#[derive(Debug)]
+pub enum NotebookErr {
+    InvalidJson(serde_json::Error),
+    /// The notebook has no `cells` array
+    MissingCells,
+}
+
+/// The cell that holds the header of a Jupyter notebook. It is tagged so it
+/// can be found again wherever it is. Notebooks are edited as text, so the
+/// rest of the notebook keeps its formatting.
+pub struct HeaderCell {
+    /// Where the cell is in the file
+    range: Range<usize>,
+    /// What to take out of the file to remove the cell
+    removal_range: Range<usize>,
+    id: Option<String>,
+    /// Text of the cell
+    pub source: String,
+}
+
+/// Where a notebook's `cells` array is in the file.
+struct Cells {
+    /// Indentation of the `cells` key, which is one level deep
+    key_indent: Vec<u8>,
+    /// Range of the array, including the brackets
+    range: Range<usize>,
+    /// Range of each cell
+    items: Vec<Range<usize>>,
+}
+
+/// Finds the tagged header cell of a notebook.
+pub fn find_header_cell(file: &[u8]) -> Result<Option<HeaderCell>, NotebookErr> {
+    let (_, cells) = parse(file)?;
+
+    for (i, item) in cells.items.iter().enumerate() {
+        let Ok(cell) = serde_json::from_slice::<Value>(&file[item.clone()]) else {
+            continue;
+        };
+        let is_header = cell["metadata"]["tags"]
+            .as_array()
+            .is_some_and(|tags| tags.iter().any(|tag| tag == HEADER_CELL_TAG));
+        if !is_header {
+            continue;
+        }
+
+        let source = match &cell["source"] {
+            Value::String(source) => source.clone(),
+            Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+            _ => String::new(),
+        };
+
+        let removal_range = if cells.items.len() == 1 {
+            cells.range.start + 1..cells.range.end - 1
+        } else if i + 1 < cells.items.len() {
+            item.start..cells.items[i + 1].start
+        } else {
+            cells.items[i - 1].end..item.end
+        };
+
+        return Ok(Some(HeaderCell {
+            range: item.clone(),
+            removal_range,
+            id: cell["id"].as_str().map(str::to_string),
+            source,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Adds a header cell at the top of a notebook.
+pub fn add_header_cell(
+    file: &[u8],
+    header_text: &str,
+    cell_type: NotebookCell,
+) -> Result<Vec<u8>, NotebookErr> {
+    let (notebook, cells) = parse(file)?;
+    let eol = line_ending(file);
+
+    // Cells have had ids since nbformat 4.5
+    let nbformat = notebook["nbformat"].as_u64().unwrap_or(4);
+    let nbformat_minor = notebook["nbformat_minor"].as_u64().unwrap_or(0);
+    let id = (nbformat > 4 || nbformat_minor >= 5).then_some(HEADER_CELL_TAG);
+
+    let unit = &cells.key_indent;
+    let Some(first) = cells.items.first() else {
+        let cell_indent = unit.repeat(2);
+        let cell = render_cell(header_text, cell_type, id, unit, &cell_indent, eol);
+        let mut array = b"[".to_vec();
+        if !unit.is_empty() {
+            array.extend_from_slice(eol);
+            array.extend_from_slice(&cell_indent);
+        }
+        array.extend_from_slice(&cell);
+        if !unit.is_empty() {
+            array.extend_from_slice(eol);
+            array.extend_from_slice(unit);
+        }
+        array.push(b']');
+        return Ok(splice(file, cells.range, &array));
+    };
+
+    let cell_indent = line_indent(file, first.start);
+    let mut text = render_cell(header_text, cell_type, id, unit, cell_indent, eol);
+    text.push(b',');
+    text.extend_from_slice(&file[cells.range.start + 1..first.start]);
+    Ok(splice(file, first.start..first.start, &text))
+}
+
+/// Replaces the text of the header cell.
+pub fn replace_header_cell(
+    file: &[u8],
+    cell: &HeaderCell,
+    header_text: &str,
+    cell_type: NotebookCell,
+) -> Result<Vec<u8>, NotebookErr> {
+    let (_, cells) = parse(file)?;
+    let cell_indent = line_indent(file, cell.range.start);
+    let text = render_cell(
+        header_text,
+        cell_type,
+        cell.id.as_deref(),
+        &cells.key_indent,
+        cell_indent,
+        line_ending(file),
+    );
+    Ok(splice(file, cell.range.clone(), &text))
+}
+
+/// Takes the header cell out of a notebook.
+pub fn remove_header_cell(file: &[u8], cell: &HeaderCell) -> Vec<u8> {
+    splice(file, cell.removal_range.clone(), b"")
+}
+
+/// Renders a header cell the way Jupyter writes cells: with sorted keys, and
+/// its source split into lines.
+fn render_cell(
+    header_text: &str,
+    cell_type: NotebookCell,
+    id: Option<&str>,
+    unit: &[u8],
+    cell_indent: &[u8],
+    eol: &[u8],
+) -> Vec<u8> {
+    let source: Vec<&str> = header_text.split_inclusive('\n').collect();
+    let mut cell = match cell_type {
+        NotebookCell::Code => json!({
+            "cell_type": "code",
+            "execution_count": null,
+            "metadata": { "tags": [HEADER_CELL_TAG] },
+            "outputs": [],
+            "source": source,
+        }),
+        NotebookCell::Markdown => json!({
+            "cell_type": "markdown",
+            "metadata": { "tags": [HEADER_CELL_TAG] },
+            "source": source,
+        }),
+    };
+    if let Some(id) = id {
+        cell["id"] = json!(id);
+    }
+    // Keep the keys sorted whatever order the map keeps them in
+    let cell: BTreeMap<String, Value> =
+        serde_json::from_value(cell).expect("cell should be an object");
+
+    if unit.is_empty() {
+        return serde_json::to_vec(&cell).expect("cell should serialize");
+    }
+
+    let mut text = Vec::new();
+    let mut serializer = Serializer::with_formatter(&mut text, PrettyFormatter::with_indent(unit));
+    serde::Serialize::serialize(&cell, &mut serializer).expect("cell should serialize");
+
+    // Indent each line to the depth of the cell
+    let mut indented_eol = eol.to_vec();
+    indented_eol.extend_from_slice(cell_indent);
+    let mut indented = Vec::with_capacity(text.len());
+    for (i, line) in text.split(|&ch| ch == b'\n').enumerate() {
+        if i > 0 {
+            indented.extend_from_slice(&indented_eol);
+        }
+        indented.extend_from_slice(line);
+    }
+    indented
+}
+
+/// Gets the whitespace before `pos` on its line, or nothing if `pos` isn't the
+/// first thing on its line.
+fn line_indent(file: &[u8], pos: usize) -> &[u8] {
+    let line_start = file[..pos]
+        .iter()
+        .rposition(|&ch| ch == b'\n')
+        .map_or(0, |i| i + 1);
+    let indent = &file[line_start..pos];
+    if line_start > 0 && indent.iter().all(|&ch| ch == b' ' || ch == b'\t') {
+        indent
+    } else {
+        b""
+    }
+}
+
+/// Parses a notebook, and finds where its cells are in the file.
+fn parse(file: &[u8]) -> Result<(Value, Cells), NotebookErr> {
+    let start = if file.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    };
+    let notebook: Value =
+        serde_json::from_slice(&file[start..]).map_err(NotebookErr::InvalidJson)?;
+    if !notebook["cells"].is_array() {
+        return Err(NotebookErr::MissingCells);
+    }
+
+    // The notebook is valid JSON, so the scan below can take that for granted
+    let mut i = skip_whitespace(file, start) + 1;
+    loop {
+        i = skip_whitespace(file, i);
+        if file[i] == b'}' {
+            return Err(NotebookErr::MissingCells);
+        }
+
+        let key_start = i;
+        let key_end = value_end(file, key_start);
+        let key: String =
+            serde_json::from_slice(&file[key_start..key_end]).map_err(NotebookErr::InvalidJson)?;
+        i = skip_whitespace(file, key_end) + 1;
+        let value_start = skip_whitespace(file, i);
+        i = value_end(file, value_start);
+
+        if key == "cells" {
+            let mut items = Vec::new();
+            let mut j = value_start + 1;
+            loop {
+                j = skip_whitespace(file, j);
+                if file[j] == b']' {
+                    break;
+                }
+                let item_end = value_end(file, j);
+                items.push(j..item_end);
+                j = skip_whitespace(file, item_end);
+                if file[j] == b',' {
+                    j += 1;
+                }
+            }
+
+            return Ok((
+                notebook,
+                Cells {
+                    key_indent: line_indent(file, key_start).to_vec(),
+                    range: value_start..i,
+                    items,
+                },
+            ));
+        }
+
+        i = skip_whitespace(file, i);
+        if file[i] == b',' {
+            i += 1;
+        }
+    }
+}
+
+fn skip_whitespace(file: &[u8], mut i: usize) -> usize {
+    while i < file.len() && file[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// Finds the end of the JSON value starting at `start`.
+fn value_end(file: &[u8], start: usize) -> usize {
+    let mut i = start;
+    match file[i] {
+        b'"' => {
+            i += 1;
+            while file[i] != b'"' {
+                if file[i] == b'\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i + 1
+        }
+        b'{' | b'[' => {
+            let mut depth = 0;
+            loop {
+                match file[i] {
+                    b'"' => {
+                        i = value_end(file, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return i + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+        }
+        _ => {
+            while i < file.len() && !matches!(file[i], b',' | b'}' | b']') {
+                if file[i].is_ascii_whitespace() {
+                    break;
+                }
+                i += 1;
+            }
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r#"{
+ "metadata": {"title": "a ] tricky \" } title"},
+ "cells": [
+  {
+   "cell_type": "code",
+   "source": ["print(1)\n"]
+  },
+  {
+   "cell_type": "markdown",
+   "source": "Done"
+  }
+ ],
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"#;
+
+    #[test]
+    fn finds_value_ends() {
+        let text = br#"{"a": [1, {"b": "]}"}], "c": "x\"y\\", "d": null, "e": -1.5e3}"#;
+        let end = |start: usize| value_end(text, start);
+        assert_eq!(end(0), text.len());
+        assert_eq!(&text[6..end(6)], br#"[1, {"b": "]}"}]"#);
+        assert_eq!(&text[29..end(29)], br#""x\"y\\""#);
+        assert_eq!(&text[44..end(44)], b"null");
+        assert_eq!(&text[55..end(55)], b"-1.5e3");
+    }
+
+    #[test]
+    fn parses_cells() {
+        let file = NOTEBOOK.as_bytes();
+        let (notebook, cells) = parse(file).unwrap();
+
+        assert_eq!(notebook["nbformat_minor"], 5);
+        assert_eq!(cells.key_indent, b" ");
+        assert!(file[cells.range.clone()].starts_with(b"["));
+        assert!(file[cells.range.clone()].ends_with(b"]"));
+        assert_eq!(cells.items.len(), 2);
+        let second: Value = serde_json::from_slice(&file[cells.items[1].clone()]).unwrap();
+        assert_eq!(second["source"], "Done");
+    }
+
+    #[test]
+    fn parses_compact_notebook_with_bom() {
+        let file = b"\xEF\xBB\xBF{\"cells\":[],\"nbformat\":4}";
+        let (_, cells) = parse(file).unwrap();
+        assert_eq!(&file[cells.range], b"[]");
+        assert!(cells.items.is_empty());
+        assert!(cells.key_indent.is_empty());
+    }
+
+    #[test]
+    fn rejects_notebook_without_cells() {
+        assert!(matches!(
+            parse(b"{\"nbformat\": 4}"),
+            Err(NotebookErr::MissingCells)
+        ));
+        assert!(matches!(
+            parse(b"{\"cells\": {}}"),
+            Err(NotebookErr::MissingCells)
+        ));
+        assert!(matches!(
+            parse(b"{\"cells\": ["),
+            Err(NotebookErr::InvalidJson(_))
+        ));
+    }
+
+    fn round_trip(original: &[u8]) {
+        let header = "# Copyright 2024 Acme\n# MIT License";
+        let added = add_header_cell(original, header, NotebookCell::Code).unwrap();
+        let notebook: Value = serde_json::from_slice(&added).unwrap();
+        assert_eq!(notebook["cells"][0]["metadata"]["tags"][0], HEADER_CELL_TAG);
+
+        let cell = find_header_cell(&added).unwrap().unwrap();
+        assert_eq!(cell.source, header);
+        assert_eq!(remove_header_cell(&added, &cell), original);
+    }
+
+    #[test]
+    fn round_trips_header_cell() {
+        round_trip(NOTEBOOK.as_bytes());
+        round_trip(NOTEBOOK.replace('\n', "\r\n").as_bytes());
+        round_trip(b"{\"cells\":[{\"cell_type\":\"code\",\"source\":[]}],\"nbformat\":4}");
+        round_trip(b"{\n \"cells\": [],\n \"nbformat\": 4\n}\n");
+        round_trip(b"{\"cells\": []}");
+    }
+
+    #[test]
+    fn keeps_notebook_formatting() {
+        let added = add_header_cell(NOTEBOOK.as_bytes(), "# A", NotebookCell::Code).unwrap();
+        let added = String::from_utf8(added).unwrap();
+        assert!(added.starts_with("{\n \"metadata\": {\"title\": \"a ] tricky \\\" } title\"},\n \"cells\": [\n  {\n   \"cell_type\": \"code\",\n"));
+        assert!(added.contains("   \"id\": \"licensesnip\",\n"));
+        // The cells that were there are left as they were
+        let first_cell = &NOTEBOOK[NOTEBOOK.find("  {").unwrap()..];
+        assert!(added.ends_with(first_cell));
+    }
+
+    #[test]
+    fn replaces_header_cell_in_place() {
+        let added = add_header_cell(NOTEBOOK.as_bytes(), "# Old", NotebookCell::Code).unwrap();
+        let cell = find_header_cell(&added).unwrap().unwrap();
+        let replaced = replace_header_cell(&added, &cell, "# New", NotebookCell::Code).unwrap();
+
+        let cell = find_header_cell(&replaced).unwrap().unwrap();
+        assert_eq!(cell.source, "# New");
+        assert_eq!(cell.id.as_deref(), Some(HEADER_CELL_TAG));
+        assert_eq!(replaced.len(), added.len());
+    }
+}
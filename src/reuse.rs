@@ -0,0 +1,360 @@
+// reuse.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    collections::BTreeSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use regex::bytes::Regex;
+use serde::Deserialize;
+
+use crate::config::{FileMode, PathGlobs};
+use crate::license::sidecar_path;
+use crate::notebook::find_header_cell;
+
+pub const REUSE_TOML_PATH: &str = "REUSE.toml";
+/// Directory holding the text of each license, as `LICENSES/<id>.txt`
+pub const LICENSES_DIR: &str = "LICENSES";
+
+/// Matches a copyright notice, capturing the text after an SPDX tag.
+const COPYRIGHT_PATTERN: &str =
+    r"(?m)(?:SPDX-FileCopyrightText:[ \t]*([^\r\n]*)|\bCopyright\b|©)[^\r\n]*";
+/// Matches an SPDX license tag, capturing the license expression.
+const LICENSE_PATTERN: &str = r"(?m)SPDX-License-Identifier:[ \t]*([^\r\n]*)";
+/// Matches text that REUSE is told to ignore, such as an example header.
+const IGNORE_PATTERN: &str = r"(?s)REUSE-IgnoreStart.*?(?:REUSE-IgnoreEnd|\z)";
+/// Comment endings that can follow a tag on the same line.
+const COMMENT_ENDS: [&str; 4] = ["*/", "-->", "#}", "%>"];
+
+#[derive(Debug)]
+pub enum ReuseErr {
+    ReadErr(io::Error),
+    TomlErr(toml::de::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReuseErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReuseErr::ReadErr(e) => write!(f, "Couldn't read {}: {}", REUSE_TOML_PATH, e),
+            ReuseErr::TomlErr(e) => write!(f, "{} isn't valid: {}", REUSE_TOML_PATH, e),
+            ReuseErr::UnsupportedVersion(version) => write!(
+                f,
+                "{} has version {}, but only version 1 is supported",
+                REUSE_TOML_PATH, version
+            ),
+        }
+    }
+}
+
+/// How the information in an annotation combines with a file's own.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Precedence {
+    /// The file's own information wins, and the annotation fills in the rest
+    #[default]
+    Closest,
+    /// Both are used
+    Aggregate,
+    /// Only the annotation is used
+    Override,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<OneOrMany> for Vec<String> {
+    fn from(value: OneOrMany) -> Self {
+        match value {
+            OneOrMany::One(one) => vec![one],
+            OneOrMany::Many(many) => many,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawAnnotation {
+    path: OneOrMany,
+    #[serde(default)]
+    precedence: Precedence,
+    #[serde(rename = "SPDX-FileCopyrightText")]
+    copyright: Option<OneOrMany>,
+    #[serde(rename = "SPDX-License-Identifier")]
+    license: Option<OneOrMany>,
+}
+
+/// Copyright and license information for the paths matching some globs.
+#[derive(Deserialize)]
+#[serde(try_from = "RawAnnotation")]
+pub struct Annotation {
+    pub paths: PathGlobs,
+    pub precedence: Precedence,
+    pub info: ReuseInfo,
+}
+
+impl TryFrom<RawAnnotation> for Annotation {
+    type Error = globset::Error;
+
+    fn try_from(raw: RawAnnotation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            paths: PathGlobs::try_from(Vec::from(raw.path))?,
+            precedence: raw.precedence,
+            info: ReuseInfo {
+                copyright: raw.copyright.map(Vec::from).unwrap_or_default(),
+                licenses: raw.license.map(Vec::from).unwrap_or_default(),
+            },
+        })
+    }
+}
+
+/// A `REUSE.toml` file, which gives copyright and license information for
+/// files that can't hold it themselves.
+#[derive(Deserialize)]
+pub struct ReuseToml {
+    pub version: u32,
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl ReuseToml {
+    /// Gets the annotation for a path relative to the project root. When
+    /// several match, the last one wins.
+    pub fn annotation(&self, file_path: &str) -> Option<&Annotation> {
+        self.annotations
+            .iter()
+            .rev()
+            .find(|annotation| annotation.paths.is_match(file_path))
+    }
+}
+
+/// Reads the `REUSE.toml` in the project root, if there is one.
+pub fn load_reuse_toml(root: &Path) -> Result<Option<ReuseToml>, ReuseErr> {
+    let text = match fs::read_to_string(root.join(REUSE_TOML_PATH)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(ReuseErr::ReadErr(e)),
+    };
+
+    let reuse_toml: ReuseToml = toml::from_str(&text).map_err(ReuseErr::TomlErr)?;
+    if reuse_toml.version != 1 {
+        return Err(ReuseErr::UnsupportedVersion(reuse_toml.version));
+    }
+    Ok(Some(reuse_toml))
+}
+
+/// Copyright notices and license expressions that apply to a file.
+#[derive(Debug, Clone, Default)]
+pub struct ReuseInfo {
+    pub copyright: Vec<String>,
+    pub licenses: Vec<String>,
+}
+
+impl ReuseInfo {
+    /// Reads the copyright notices and SPDX license tags in a file.
+    pub fn from_text(text: &[u8]) -> Self {
+        let ignore = Regex::new(IGNORE_PATTERN).expect("ignore pattern should be a valid regex");
+        let text = ignore.replace_all(text, &b""[..]);
+
+        let copyright_pattern =
+            Regex::new(COPYRIGHT_PATTERN).expect("copyright pattern should be a valid regex");
+        let copyright = copyright_pattern
+            .captures_iter(&text)
+            .map(|captures| {
+                let notice = captures.get(1).unwrap_or_else(|| captures.get(0).unwrap());
+                tag_value(notice.as_bytes())
+            })
+            .collect();
+
+        let license_pattern =
+            Regex::new(LICENSE_PATTERN).expect("license pattern should be a valid regex");
+        let licenses = license_pattern
+            .captures_iter(&text)
+            .map(|captures| tag_value(captures.get(1).unwrap().as_bytes()))
+            .filter(|expression| !expression.is_empty())
+            .collect();
+
+        Self {
+            copyright,
+            licenses,
+        }
+    }
+
+    fn combine(own: Self, annotation: &Self, precedence: Precedence) -> Self {
+        let pick = |own: Vec<String>, annotation: &Vec<String>| match precedence {
+            Precedence::Closest if own.is_empty() => annotation.clone(),
+            Precedence::Closest => own,
+            Precedence::Aggregate => own.into_iter().chain(annotation.clone()).collect(),
+            Precedence::Override => annotation.clone(),
+        };
+
+        Self {
+            copyright: pick(own.copyright, &annotation.copyright),
+            licenses: pick(own.licenses, &annotation.licenses),
+        }
+    }
+}
+
+/// Trims a tag's value and any comment ending after it.
+fn tag_value(value: &[u8]) -> String {
+    let mut value = String::from_utf8_lossy(value).trim().to_string();
+    while let Some(end) = COMMENT_ENDS.iter().find(|end| value.ends_with(*end)) {
+        value.truncate(value.len() - end.len());
+        value = value.trim_end().to_string();
+    }
+    value
+}
+
+/// Gets the copyright and license information of a file, from its sidecar
+/// `.license` file if it has one, from its own text otherwise, and from
+/// `REUSE.toml`. Only the header cell of a notebook is read, since the rest
+/// of it is JSON.
+pub fn file_info(
+    path: &Path,
+    file_path: &str,
+    mode: FileMode,
+    reuse_toml: Option<&ReuseToml>,
+) -> io::Result<ReuseInfo> {
+    let annotation = reuse_toml.and_then(|reuse_toml| reuse_toml.annotation(file_path));
+    if let Some(annotation) = annotation {
+        if annotation.precedence == Precedence::Override {
+            return Ok(annotation.info.clone());
+        }
+    }
+
+    let own = match fs::read(sidecar_path(path)) {
+        Ok(sidecar) => ReuseInfo::from_text(&sidecar),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let f_bytes = fs::read(path)?;
+            if mode == FileMode::Notebook {
+                match find_header_cell(&f_bytes).ok().flatten() {
+                    Some(cell) => ReuseInfo::from_text(cell.source.as_bytes()),
+                    None => ReuseInfo::default(),
+                }
+            } else {
+                ReuseInfo::from_text(&f_bytes)
+            }
+        }
+        Err(e) => return Err(e),
+    };
+
+    Ok(match annotation {
+        Some(annotation) => ReuseInfo::combine(own, &annotation.info, annotation.precedence),
+        None => own,
+    })
+}
+
+/// Gets the identifiers that have a license text in the `LICENSES` directory.
+pub fn license_texts(root: &Path) -> BTreeSet<String> {
+    let Ok(entries) = fs::read_dir(root.join(LICENSES_DIR)) else {
+        return BTreeSet::new();
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path: PathBuf = entry.ok()?.path();
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+/// Reads the text of a license from the `LICENSES` directory.
+pub fn license_text(root: &Path, id: &str) -> Option<String> {
+    fs::read_dir(root.join(LICENSES_DIR))
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .find(|path| path.file_stem().is_some_and(|stem| stem == id))
+        .and_then(|path| fs::read_to_string(path).ok())
+}
+
+/// Writes a `REUSE.toml` annotation for some paths, from the lines of an SPDX
+/// header.
+pub fn render_annotation(paths: &[String], spdx_lines: &[String]) -> String {
+    let quote = |text: &str| toml::Value::String(text.to_string()).to_string();
+
+    let mut text = String::from("[[annotations]]\n");
+    match paths {
+        [path] => text.push_str(&format!("path = {}\n", quote(path))),
+        _ => {
+            text.push_str("path = [\n");
+            for path in paths {
+                text.push_str(&format!("    {},\n", quote(path)));
+            }
+            text.push_str("]\n");
+        }
+    }
+    for line in spdx_lines {
+        if let Some((tag, value)) = line.split_once(':') {
+            text.push_str(&format!("{} = {}\n", tag.trim(), quote(value.trim())));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "metadata": {"tags": ["licensesnip"]},
+   "source": [
+    "# SPDX-FileCopyrightText: 2024 Acme\n",
+    "# SPDX-License-Identifier: MIT"
+   ]
+  }
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    #[test]
+    fn reads_tags_from_text() {
+        let info = ReuseInfo::from_text(
+            b"/* SPDX-FileCopyrightText: 2024 Acme */\n// SPDX-License-Identifier: MIT\n",
+        );
+        assert_eq!(info.copyright, ["2024 Acme"]);
+        assert_eq!(info.licenses, ["MIT"]);
+    }
+
+    #[test]
+    fn reads_tags_from_notebook_header_cell() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("n.ipynb");
+        fs::write(&path, NOTEBOOK).unwrap();
+
+        let info = file_info(&path, "n.ipynb", FileMode::Notebook, None).unwrap();
+        assert_eq!(info.copyright, ["2024 Acme"]);
+        assert_eq!(info.licenses, ["MIT"]);
+    }
+}
@@ -0,0 +1,440 @@
+// sbom.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Licenses that SPDX documents themselves are under.
+const DATA_LICENSE: &str = "CC0-1.0";
+/// Stands for information that wasn't found or isn't known.
+pub const NOASSERTION: &str = "NOASSERTION";
+
+/// A file listed in an SPDX document.
+pub struct SpdxFile {
+    /// Path of the file, starting with `./`
+    pub name: String,
+    pub sha1: String,
+    pub sha256: String,
+    /// Identifiers of the licenses found in the file
+    pub licenses: Vec<String>,
+    /// Copyright notices found in the file
+    pub copyright: Vec<String>,
+}
+
+impl SpdxFile {
+    pub fn new(
+        name: String,
+        contents: &[u8],
+        licenses: Vec<String>,
+        copyright: Vec<String>,
+    ) -> Self {
+        Self {
+            name,
+            sha1: format!("{:x}", Sha1::digest(contents)),
+            sha256: format!("{:x}", Sha256::digest(contents)),
+            licenses,
+            copyright,
+        }
+    }
+
+    fn license_info(&self) -> Vec<&str> {
+        if self.licenses.is_empty() {
+            return vec![NOASSERTION];
+        }
+        self.licenses.iter().map(String::as_str).collect()
+    }
+
+    fn copyright_text(&self) -> String {
+        if self.copyright.is_empty() {
+            return String::from(NOASSERTION);
+        }
+        self.copyright.join("\n")
+    }
+}
+
+/// A license that isn't on the SPDX license list, such as `LicenseRef-Acme`.
+pub struct ExtractedLicense {
+    pub id: String,
+    /// Text of the license, if it could be found
+    pub text: Option<String>,
+}
+
+/// An SPDX 2.3 document describing a project as a single package, listing each
+/// of its files with their checksums, licenses and copyright notices.
+pub struct SpdxDocument {
+    /// Name of the project
+    pub name: String,
+    /// Creation time, as `YYYY-MM-DDThh:mm:ssZ`
+    pub created: String,
+    /// License declared for the whole project
+    pub declared_license: Option<String>,
+    pub files: Vec<SpdxFile>,
+    pub extracted_licenses: Vec<ExtractedLicense>,
+}
+
+impl SpdxDocument {
+    /// Computes the package verification code, which is the SHA1 of the
+    /// sorted SHA1s of the package's files.
+    fn verification_code(&self) -> String {
+        let mut checksums: Vec<&str> = self.files.iter().map(|f| f.sha1.as_str()).collect();
+        checksums.sort_unstable();
+        format!("{:x}", Sha1::digest(checksums.concat()))
+    }
+
+    /// A URI that is unique to this version of the project, made up from its
+    /// contents so that it doesn't need a random number.
+    fn namespace(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.verification_code());
+        hasher.update(&self.created);
+        let hash = format!("{:x}", hasher.finalize());
+        format!(
+            "https://spdx.org/spdxdocs/{}-{}",
+            self.name
+                .replace(|ch: char| !ch.is_ascii_alphanumeric(), "-"),
+            &hash[..32]
+        )
+    }
+
+    /// Identifiers of all the licenses found in the files, in order.
+    fn license_info_from_files(&self) -> Vec<&str> {
+        let mut licenses: Vec<&str> = self.files.iter().flat_map(SpdxFile::license_info).collect();
+        licenses.sort_unstable();
+        licenses.dedup();
+        licenses
+    }
+
+    fn creator() -> String {
+        format!("Tool: licensesnip-{}", env!("CARGO_PKG_VERSION"))
+    }
+
+    fn file_id(index: usize) -> String {
+        format!("SPDXRef-File-{}", index + 1)
+    }
+
+    /// Writes the document in the SPDX JSON format.
+    pub fn to_json(&self) -> String {
+        let files: Vec<Value> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                json!({
+                    "fileName": file.name,
+                    "SPDXID": Self::file_id(i),
+                    "checksums": [
+                        { "algorithm": "SHA1", "checksumValue": file.sha1 },
+                        { "algorithm": "SHA256", "checksumValue": file.sha256 },
+                    ],
+                    "licenseConcluded": NOASSERTION,
+                    "licenseInfoInFiles": file.license_info(),
+                    "copyrightText": file.copyright_text(),
+                })
+            })
+            .collect();
+
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": "SPDXRef-Package",
+        })];
+        relationships.extend((0..self.files.len()).map(|i| {
+            json!({
+                "spdxElementId": "SPDXRef-Package",
+                "relationshipType": "CONTAINS",
+                "relatedSpdxElement": Self::file_id(i),
+            })
+        }));
+
+        let mut document = json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": DATA_LICENSE,
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": self.name,
+            "documentNamespace": self.namespace(),
+            "creationInfo": {
+                "created": self.created,
+                "creators": [Self::creator()],
+            },
+            "packages": [{
+                "name": self.name,
+                "SPDXID": "SPDXRef-Package",
+                "downloadLocation": NOASSERTION,
+                "filesAnalyzed": true,
+                "packageVerificationCode": {
+                    "packageVerificationCodeValue": self.verification_code(),
+                },
+                "licenseConcluded": NOASSERTION,
+                "licenseDeclared": self.declared_license.as_deref().unwrap_or(NOASSERTION),
+                "licenseInfoFromFiles": self.license_info_from_files(),
+                "copyrightText": NOASSERTION,
+            }],
+            "files": files,
+            "relationships": relationships,
+        });
+
+        if !self.extracted_licenses.is_empty() {
+            document["hasExtractedLicensingInfos"] = self
+                .extracted_licenses
+                .iter()
+                .map(|license| {
+                    json!({
+                        "licenseId": license.id,
+                        "extractedText": license.text.as_deref().unwrap_or(NOASSERTION),
+                    })
+                })
+                .collect();
+        }
+
+        let mut text = serde_json::to_string_pretty(&document).expect("document should serialize");
+        text.push('\n');
+        text
+    }
+
+    /// Writes the document in the SPDX tag-value format.
+    pub fn to_tag_value(&self) -> String {
+        let mut text = String::new();
+        let t = &mut text;
+
+        tag(t, "SPDXVersion", "SPDX-2.3");
+        tag(t, "DataLicense", DATA_LICENSE);
+        tag(t, "SPDXID", "SPDXRef-DOCUMENT");
+        tag(t, "DocumentName", &self.name);
+        tag(t, "DocumentNamespace", &self.namespace());
+        tag(t, "Creator", &Self::creator());
+        tag(t, "Created", &self.created);
+
+        t.push('\n');
+        tag(t, "PackageName", &self.name);
+        tag(t, "SPDXID", "SPDXRef-Package");
+        tag(t, "PackageDownloadLocation", NOASSERTION);
+        tag(t, "FilesAnalyzed", "true");
+        tag(t, "PackageVerificationCode", &self.verification_code());
+        tag(t, "PackageLicenseConcluded", NOASSERTION);
+        for license in self.license_info_from_files() {
+            tag(t, "PackageLicenseInfoFromFiles", license);
+        }
+        tag(
+            t,
+            "PackageLicenseDeclared",
+            self.declared_license.as_deref().unwrap_or(NOASSERTION),
+        );
+        tag(t, "PackageCopyrightText", NOASSERTION);
+        tag(
+            t,
+            "Relationship",
+            "SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package",
+        );
+
+        for (i, file) in self.files.iter().enumerate() {
+            t.push('\n');
+            tag(t, "FileName", &file.name);
+            tag(t, "SPDXID", &Self::file_id(i));
+            tag(t, "FileChecksum", &format!("SHA1: {}", file.sha1));
+            tag(t, "FileChecksum", &format!("SHA256: {}", file.sha256));
+            tag(t, "LicenseConcluded", NOASSERTION);
+            for license in file.license_info() {
+                tag(t, "LicenseInfoInFile", license);
+            }
+            tag(t, "FileCopyrightText", &file.copyright_text());
+            tag(
+                t,
+                "Relationship",
+                &format!("SPDXRef-Package CONTAINS {}", Self::file_id(i)),
+            );
+        }
+
+        for license in &self.extracted_licenses {
+            t.push('\n');
+            tag(t, "LicenseID", &license.id);
+            tag(
+                t,
+                "ExtractedText",
+                license.text.as_deref().unwrap_or(NOASSERTION),
+            );
+        }
+
+        text
+    }
+}
+
+/// Writes a tag of the tag-value format, wrapping values that span several
+/// lines in `<text>` tags.
+fn tag(text: &mut String, name: &str, value: &str) {
+    // Writing to a String can't fail
+    let _ = if value.contains('\n') {
+        writeln!(text, "{}: <text>{}</text>", name, value)
+    } else {
+        writeln!(text, "{}: {}", name, value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> SpdxDocument {
+        SpdxDocument {
+            name: String::from("my project"),
+            created: String::from("2024-01-02T03:04:05Z"),
+            declared_license: Some(String::from("MIT OR Apache-2.0")),
+            files: vec![
+                SpdxFile::new(
+                    String::from("./src/main.rs"),
+                    b"fn main() {}\n",
+                    vec![String::from("MIT"), String::from("LicenseRef-Acme")],
+                    vec![String::from("2024 Acme"), String::from("2023 Jane Doe")],
+                ),
+                SpdxFile::new(
+                    String::from("./logo.png"),
+                    b"\x89PNG",
+                    Vec::new(),
+                    Vec::new(),
+                ),
+            ],
+            extracted_licenses: vec![ExtractedLicense {
+                id: String::from("LicenseRef-Acme"),
+                text: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn computes_the_verification_code_from_sorted_checksums() {
+        let document = document();
+        let mut checksums = [
+            document.files[0].sha1.clone(),
+            document.files[1].sha1.clone(),
+        ];
+        checksums.sort();
+        let expected = format!("{:x}", Sha1::digest(checksums.concat()));
+        assert_eq!(document.verification_code(), expected);
+
+        let mut reversed = document;
+        reversed.files.reverse();
+        assert_eq!(reversed.verification_code(), expected);
+    }
+
+    #[test]
+    fn writes_json() {
+        let document = document();
+        let json: Value = serde_json::from_str(&document.to_json()).unwrap();
+
+        assert_eq!(json["spdxVersion"], "SPDX-2.3");
+        assert_eq!(json["dataLicense"], DATA_LICENSE);
+        assert_eq!(json["creationInfo"]["created"], "2024-01-02T03:04:05Z");
+        assert!(json["documentNamespace"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://spdx.org/spdxdocs/my-project-"));
+
+        let package = &json["packages"][0];
+        assert_eq!(package["filesAnalyzed"], true);
+        assert_eq!(
+            package["packageVerificationCode"]["packageVerificationCodeValue"],
+            document.verification_code()
+        );
+        assert_eq!(package["licenseDeclared"], "MIT OR Apache-2.0");
+        assert_eq!(
+            package["licenseInfoFromFiles"],
+            json!(["LicenseRef-Acme", "MIT", NOASSERTION])
+        );
+
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["fileName"], "./src/main.rs");
+        assert_eq!(files[0]["SPDXID"], "SPDXRef-File-1");
+        assert_eq!(
+            files[0]["checksums"][0]["checksumValue"],
+            document.files[0].sha1
+        );
+        assert_eq!(
+            files[0]["licenseInfoInFiles"],
+            json!(["MIT", "LicenseRef-Acme"])
+        );
+        assert_eq!(files[0]["copyrightText"], "2024 Acme\n2023 Jane Doe");
+        assert_eq!(files[1]["licenseInfoInFiles"], json!([NOASSERTION]));
+        assert_eq!(files[1]["copyrightText"], NOASSERTION);
+
+        let relationships = json["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 3);
+        assert_eq!(relationships[2]["relatedSpdxElement"], "SPDXRef-File-2");
+
+        let extracted = &json["hasExtractedLicensingInfos"][0];
+        assert_eq!(extracted["licenseId"], "LicenseRef-Acme");
+        assert_eq!(extracted["extractedText"], NOASSERTION);
+    }
+
+    #[test]
+    fn leaves_out_extracted_licenses_when_there_are_none() {
+        let mut document = document();
+        document.extracted_licenses.clear();
+        document.declared_license = None;
+        let json: Value = serde_json::from_str(&document.to_json()).unwrap();
+
+        assert!(json.get("hasExtractedLicensingInfos").is_none());
+        assert_eq!(json["packages"][0]["licenseDeclared"], NOASSERTION);
+    }
+
+    #[test]
+    fn writes_tag_value() {
+        let document = document();
+        let text = document.to_tag_value();
+        let lines: Vec<&str> = text.lines().collect();
+
+        for line in [
+            "SPDXVersion: SPDX-2.3",
+            "DocumentName: my project",
+            "Created: 2024-01-02T03:04:05Z",
+            "FilesAnalyzed: true",
+            "PackageLicenseDeclared: MIT OR Apache-2.0",
+            "PackageLicenseInfoFromFiles: NOASSERTION",
+            "Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-Package",
+            "FileName: ./src/main.rs",
+            "SPDXID: SPDXRef-File-1",
+            "LicenseInfoInFile: MIT",
+            "LicenseInfoInFile: LicenseRef-Acme",
+            "FileName: ./logo.png",
+            "LicenseInfoInFile: NOASSERTION",
+            "FileCopyrightText: NOASSERTION",
+            "Relationship: SPDXRef-Package CONTAINS SPDXRef-File-2",
+            "LicenseID: LicenseRef-Acme",
+            "ExtractedText: NOASSERTION",
+        ] {
+            assert!(lines.contains(&line), "missing {:?} in\n{}", line, text);
+        }
+        assert!(lines.contains(
+            &format!("PackageVerificationCode: {}", document.verification_code()).as_str()
+        ));
+        assert!(
+            lines.contains(&format!("FileChecksum: SHA256: {}", document.files[0].sha256).as_str())
+        );
+
+        // Values on several lines are wrapped in <text> tags
+        assert!(text.contains("FileCopyrightText: <text>2024 Acme\n2023 Jane Doe</text>\n"));
+    }
+}
@@ -0,0 +1,303 @@
+// spdx.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt;
+
+/// An error in an SPDX license expression, with a description of what's wrong.
+#[derive(Debug)]
+pub struct SpdxErr(String);
+
+impl fmt::Display for SpdxErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SpdxErr {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Word(&'a str),
+}
+
+fn tokenize(expression: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::<Token>::new();
+    let mut word_start: Option<usize> = None;
+
+    for (i, ch) in expression.char_indices() {
+        if ch == '(' || ch == ')' || ch.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                tokens.push(Token::Word(&expression[start..i]));
+            }
+            match ch {
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                _ => {}
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+
+    if let Some(start) = word_start {
+        tokens.push(Token::Word(&expression[start..]));
+    }
+
+    tokens
+}
+
+fn is_operator(word: &str) -> bool {
+    matches!(word, "AND" | "OR" | "WITH")
+}
+
+fn is_idstring(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '.')
+}
+
+fn is_license_ref(text: &str) -> bool {
+    let license_ref = match text.strip_prefix("DocumentRef-") {
+        Some(rest) => match rest.split_once(':') {
+            Some((document, license_ref)) if is_idstring(document) => license_ref,
+            _ => return false,
+        },
+        None => text,
+    };
+
+    match license_ref.strip_prefix("LicenseRef-") {
+        Some(id) => is_idstring(id),
+        None => false,
+    }
+}
+
+/// Recursive descent parser for the grammar in annex D of the SPDX
+/// specification. `OR` binds weaker than `AND`, which binds weaker than `WITH`.
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn next_word_is(&self, word: &str) -> bool {
+        self.peek() == Some(&Token::Word(word))
+    }
+
+    fn parse_or(&mut self) -> Result<(), SpdxErr> {
+        self.parse_and()?;
+        while self.next_word_is("OR") {
+            self.position += 1;
+            self.parse_and()?;
+        }
+        Ok(())
+    }
+
+    fn parse_and(&mut self) -> Result<(), SpdxErr> {
+        self.parse_with()?;
+        while self.next_word_is("AND") {
+            self.position += 1;
+            self.parse_with()?;
+        }
+        Ok(())
+    }
+
+    fn parse_with(&mut self) -> Result<(), SpdxErr> {
+        let is_simple = self.parse_atom()?;
+        if self.next_word_is("WITH") {
+            if !is_simple {
+                return Err(SpdxErr(String::from(
+                    "WITH must follow a license identifier, not a parenthesized expression",
+                )));
+            }
+            self.position += 1;
+            match self.peek() {
+                Some(Token::Word(exception)) if !is_operator(exception) => {
+                    if !is_idstring(exception) {
+                        return Err(SpdxErr(format!(
+                            "\"{}\" isn't a valid license exception identifier",
+                            exception
+                        )));
+                    }
+                    self.position += 1;
+                }
+                _ => {
+                    return Err(SpdxErr(String::from(
+                        "WITH must be followed by a license exception identifier",
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a license identifier or a parenthesized expression. Returns
+    /// whether it was a license identifier.
+    fn parse_atom(&mut self) -> Result<bool, SpdxErr> {
+        match self.peek() {
+            Some(Token::Open) => {
+                self.position += 1;
+                self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(SpdxErr(String::from("Missing closing parenthesis")));
+                }
+                self.position += 1;
+                Ok(false)
+            }
+            Some(Token::Word(word)) if !is_operator(word) => {
+                let id = word.strip_suffix('+').unwrap_or(word);
+                let is_ref = word.starts_with("LicenseRef-") || word.starts_with("DocumentRef-");
+                if (is_ref || !is_idstring(id)) && !is_license_ref(word) {
+                    return Err(SpdxErr(format!(
+                        "\"{}\" isn't a valid license identifier",
+                        word
+                    )));
+                }
+                self.position += 1;
+                Ok(true)
+            }
+            Some(Token::Word(word)) => Err(SpdxErr(format!(
+                "Expected a license identifier but found {}",
+                word
+            ))),
+            Some(Token::Close) => Err(SpdxErr(String::from("Unexpected closing parenthesis"))),
+            None => Err(SpdxErr(String::from("Expected a license identifier"))),
+        }
+    }
+}
+
+/// Checks that an SPDX license expression such as `MIT OR Apache-2.0` is
+/// syntactically valid. Identifiers aren't checked against the SPDX license list.
+pub fn validate_expression(expression: &str) -> Result<(), SpdxErr> {
+    let mut parser = Parser {
+        tokens: tokenize(expression),
+        position: 0,
+    };
+
+    parser.parse_or()?;
+
+    match parser.peek() {
+        None => Ok(()),
+        Some(Token::Word(word)) => Err(SpdxErr(format!("Unexpected \"{}\"", word))),
+        Some(Token::Open) => Err(SpdxErr(String::from("Unexpected opening parenthesis"))),
+        Some(Token::Close) => Err(SpdxErr(String::from("Unexpected closing parenthesis"))),
+    }
+}
+
+/// Gets the license and exception identifiers used in a valid SPDX license
+/// expression, without any `+` suffix.
+pub fn expression_ids(expression: &str) -> Vec<&str> {
+    tokenize(expression)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Word(word) if !is_operator(word) => Some(word.strip_suffix('+').unwrap_or(word)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Gets the license identifiers used in a valid SPDX license expression,
+/// without any `+` suffix, leaving out the exceptions named after `WITH`.
+pub fn expression_license_ids(expression: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut after_with = false;
+    for token in tokenize(expression) {
+        if let Token::Word(word) = token {
+            if word == "WITH" {
+                after_with = true;
+            } else if is_operator(word) {
+                after_with = false;
+            } else if !std::mem::take(&mut after_with) {
+                ids.push(word.strip_suffix('+').unwrap_or(word));
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_expressions() {
+        for expression in [
+            "MIT",
+            "GPL-2.0+",
+            "MIT OR Apache-2.0",
+            "Apache-2.0 WITH LLVM-exception",
+            "(MIT OR Apache-2.0) AND BSD-3-Clause",
+            "LicenseRef-Acme-1.0",
+            "DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2",
+            "MIT AND (LGPL-2.1-or-later OR BSD-3-Clause WITH Classpath-exception-2.0)",
+        ] {
+            assert!(validate_expression(expression).is_ok(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "",
+            "MIT OR",
+            "OR MIT",
+            "MIT Apache-2.0",
+            "(MIT",
+            "MIT)",
+            "()",
+            "MIT WITH",
+            "(MIT OR Apache-2.0) WITH LLVM-exception",
+            "MIT WITH Foo_exception",
+            "MIT/X11",
+            "LicenseRef-",
+            "DocumentRef-:LicenseRef-A",
+        ] {
+            assert!(validate_expression(expression).is_err(), "{}", expression);
+        }
+    }
+
+    #[test]
+    fn operators_are_case_sensitive() {
+        assert!(validate_expression("MIT or Apache-2.0").is_err());
+    }
+
+    #[test]
+    fn license_ids_leave_out_exceptions() {
+        let expression = "(Apache-2.0 WITH LLVM-exception OR GPL-2.0+) AND MIT";
+        assert_eq!(
+            expression_ids(expression),
+            ["Apache-2.0", "LLVM-exception", "GPL-2.0", "MIT"]
+        );
+        assert_eq!(
+            expression_license_ids(expression),
+            ["Apache-2.0", "GPL-2.0", "MIT"]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+// template.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fmt;
+
+/// Prefix of placeholders that read an environment variable, e.g. `%ENV:USER%`.
+pub const ENV_PREFIX: &str = "ENV:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Text(String),
+    Variable(String),
+}
+
+/// A license template with `%NAME%` placeholders. `%%` is an escaped `%`, and a
+/// `%` that doesn't start a placeholder is kept as it is.
+#[derive(Debug, Clone)]
+pub struct Template {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug)]
+pub enum TemplateErr {
+    UnknownVariable(String),
+    UnsetEnvVariable(String),
+    /// A placeholder that differs from file to file, where one value is needed
+    /// for many files
+    PerFileVariable(String),
+}
+
+impl fmt::Display for TemplateErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateErr::UnknownVariable(name) => write!(
+                f,
+                "Unknown placeholder %{}%. Define it under \"variables\" in your config, or write %% for a literal %.",
+                name
+            ),
+            TemplateErr::UnsetEnvVariable(name) => write!(
+                f,
+                "Placeholder %{}{}% refers to an environment variable that isn't set.",
+                ENV_PREFIX, name
+            ),
+            TemplateErr::PerFileVariable(name) => write!(
+                f,
+                "Placeholder %{}% is different for each file, so it can't be used for many files at once.",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateErr {}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// Gets the length of the variable name at the start of `text`, if `text`
+/// starts with a name followed by the closing `%`.
+fn variable_name_len(text: &str) -> Option<usize> {
+    let name_start = if text.starts_with(ENV_PREFIX) {
+        ENV_PREFIX.len()
+    } else {
+        0
+    };
+
+    let first = text[name_start..].chars().next()?;
+    if !(first.is_ascii_alphabetic() || first == '_') {
+        return None;
+    }
+
+    let name_len = text[name_start..]
+        .find(|ch: char| !is_name_char(ch))
+        .unwrap_or(text.len() - name_start);
+    let end = name_start + name_len;
+
+    if text[end..].starts_with('%') {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Self {
+        let mut tokens = Vec::<Token>::new();
+        let mut literal = String::new();
+        let mut rest = text;
+
+        while let Some(i) = rest.find('%') {
+            literal.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+
+            if let Some(after_escape) = rest.strip_prefix('%') {
+                literal.push('%');
+                rest = after_escape;
+            } else if let Some(len) = variable_name_len(rest) {
+                if !literal.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut literal)));
+                }
+                tokens.push(Token::Variable(rest[..len].to_string()));
+                rest = &rest[len + 1..];
+            } else {
+                literal.push('%');
+            }
+        }
+
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            tokens.push(Token::Text(literal));
+        }
+
+        Self { tokens }
+    }
+
+    /// Gets the names of all placeholders in the template, without the `%`s.
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().filter_map(|token| match token {
+            Token::Variable(name) => Some(name.as_str()),
+            Token::Text(_) => None,
+        })
+    }
+
+    pub fn uses_variable(&self, name: &str) -> bool {
+        self.variables().any(|v| v == name)
+    }
+
+    /// Fills in the placeholders with the values returned by `resolve`.
+    pub fn render<F>(&self, resolve: F) -> Result<String, TemplateErr>
+    where
+        F: Fn(&str) -> Result<String, TemplateErr>,
+    {
+        let mut text = String::new();
+        for token in &self.tokens {
+            match token {
+                Token::Text(t) => text.push_str(t),
+                Token::Variable(name) => text.push_str(&resolve(name)?),
+            }
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        Template::parse(text).tokens
+    }
+
+    fn text(t: &str) -> Token {
+        Token::Text(t.to_string())
+    }
+
+    fn variable(name: &str) -> Token {
+        Token::Variable(name.to_string())
+    }
+
+    #[test]
+    fn parses_placeholders() {
+        assert_eq!(
+            tokens("Copyright %YEAR% %HOLDER_NAME%"),
+            vec![
+                text("Copyright "),
+                variable("YEAR"),
+                text(" "),
+                variable("HOLDER_NAME")
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_escaped_percent() {
+        assert_eq!(tokens("100%% free"), vec![text("100% free")]);
+        assert_eq!(tokens("%%YEAR%%"), vec![text("%YEAR%")]);
+    }
+
+    #[test]
+    fn keeps_stray_percent() {
+        assert_eq!(tokens("50% off"), vec![text("50% off")]);
+        assert_eq!(tokens("%1 and %"), vec![text("%1 and %")]);
+        assert_eq!(tokens("%NOT CLOSED"), vec![text("%NOT CLOSED")]);
+    }
+
+    #[test]
+    fn parses_env_placeholder() {
+        assert_eq!(
+            tokens("By %ENV:USER%."),
+            vec![text("By "), variable("ENV:USER"), text(".")]
+        );
+        assert_eq!(tokens("%ENV:%"), vec![text("%ENV:%")]);
+    }
+
+    #[test]
+    fn renders_values() {
+        let template = Template::parse("%A%-%%-%B%");
+        let rendered = template.render(|name| Ok(name.to_lowercase()));
+        assert_eq!(rendered.unwrap(), "a-%-b");
+    }
+
+    #[test]
+    fn render_fails_on_unknown_variable() {
+        let template = Template::parse("%MISSING%");
+        let rendered = template.render(|name| Err(TemplateErr::UnknownVariable(name.to_string())));
+        assert!(matches!(rendered, Err(TemplateErr::UnknownVariable(name)) if name == "MISSING"));
+    }
+}
@@ -0,0 +1,211 @@
+// write.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, Write},
+    path::Path,
+};
+
+use colored::Colorize;
+use filetime::FileTime;
+use tempfile::NamedTempFile;
+
+/// Replaces the contents of a file, so that it is never left half-written.
+///
+/// The new contents go to a temporary file next to the original, which is
+/// synced to disk and then renamed over it. The original's mode, owner and
+/// extended attributes are kept, and so is its modification time if
+/// `preserve_mtime` is set. Symlinks are written through, to the file they
+/// point to.
+pub fn write_file(path: &Path, contents: &[u8], preserve_mtime: bool) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let dir = match path.parent() {
+        Some(d) => d,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "file has no parent directory",
+            ))
+        }
+    };
+    let metadata = fs::metadata(&path)?;
+
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".licensesnip-")
+        .tempfile_in(dir)?;
+    tmp.write_all(contents)?;
+
+    // Changing the owner clears the setuid and setgid bits, so the mode is
+    // set after it
+    copy_owner(&path, &metadata, &tmp)?;
+    tmp.as_file().set_permissions(metadata.permissions())?;
+    copy_xattrs(&path, tmp.path());
+    if preserve_mtime {
+        filetime::set_file_handle_times(
+            tmp.as_file(),
+            Some(FileTime::from_last_access_time(&metadata)),
+            Some(FileTime::from_last_modification_time(&metadata)),
+        )?;
+    }
+    tmp.as_file().sync_all()?;
+
+    tmp.persist(&path).map_err(|e| e.error)?;
+    sync_dir(dir);
+
+    Ok(())
+}
+
+/// Writes a new file the same way as `write_file`, with the permissions new
+/// files get by default.
+pub fn create_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if d.as_os_str().is_empty() => Path::new("."),
+        Some(d) => d,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "file has no parent directory",
+            ))
+        }
+    };
+
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".licensesnip-");
+    // Temporary files are only readable by their owner unless told otherwise
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+
+    let mut tmp = builder.tempfile_in(dir)?;
+    tmp.write_all(contents)?;
+    tmp.as_file().sync_all()?;
+
+    tmp.persist(path).map_err(|e| e.error)?;
+    sync_dir(dir);
+
+    Ok(())
+}
+
+/// Syncs a directory, so that a rename in it is on disk. Not every platform can
+/// open a directory for this, so it's only best effort.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+/// Gives the new file the owner and group of the old one. Only root can give a
+/// file away, and other users can only pick a group they're in, so a change
+/// that isn't permitted is skipped with a warning rather than failing the
+/// write.
+#[cfg(unix)]
+fn copy_owner(path: &Path, metadata: &Metadata, tmp: &NamedTempFile) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let tmp_metadata = tmp.as_file().metadata()?;
+    if tmp_metadata.uid() == metadata.uid() && tmp_metadata.gid() == metadata.gid() {
+        return Ok(());
+    }
+
+    match std::os::unix::fs::fchown(tmp.as_file(), Some(metadata.uid()), Some(metadata.gid())) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let warning = format!(
+                "⚠ Couldn't keep the owner and group of {}, so it now has yours: {}",
+                path.display(),
+                e
+            );
+            println!("{}", warning.yellow());
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_path: &Path, _metadata: &Metadata, _tmp: &NamedTempFile) -> io::Result<()> {
+    Ok(())
+}
+
+/// Copies the extended attributes of a file. Attributes that can't be read or
+/// set, such as ones needing extra privileges, are left out.
+#[cfg(unix)]
+fn copy_xattrs(from: &Path, to: &Path) {
+    let Ok(names) = xattr::list(from) else {
+        return;
+    };
+
+    for name in names {
+        if let Ok(Some(value)) = xattr::get(from, &name) {
+            let _ = xattr::set(to, &name, &value);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_xattrs(_from: &Path, _to: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_mtime_if_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.rs");
+        fs::write(&path, "old").unwrap();
+        let mtime = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(&path, mtime).unwrap();
+
+        write_file(&path, b"new", true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        let metadata = fs::metadata(&path).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&metadata), mtime);
+
+        write_file(&path, b"newer", false).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert_ne!(FileTime::from_last_modification_time(&metadata), mtime);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_mode_and_owner() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.sh");
+        fs::write(&path, "echo old").unwrap();
+        // The setgid bit is lost if it's set before the owner
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o2751)).unwrap();
+        let before = fs::metadata(&path).unwrap();
+
+        write_file(&path, b"echo new", false).unwrap();
+        let after = fs::metadata(&path).unwrap();
+        assert_eq!(after.permissions().mode() & 0o7777, 0o2751);
+        assert_eq!((after.uid(), after.gid()), (before.uid(), before.gid()));
+        assert_eq!(fs::read(&path).unwrap(), b"echo new");
+    }
+}
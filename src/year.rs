@@ -0,0 +1,198 @@
+// year.rs
+//
+// MIT License
+//
+// Copyright (c) 2025 Ken Zhou
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::env;
+use std::fmt;
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+
+use crate::config::YearPolicy;
+
+/// Environment variable used by reproducible builds to pin the current time.
+/// See https://reproducible-builds.org/specs/source-date-epoch/
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+#[derive(Debug)]
+pub enum CurrentYearErr {
+    InvalidSourceDateEpoch(String),
+}
+
+impl fmt::Display for CurrentYearErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrentYearErr::InvalidSourceDateEpoch(value) => write!(
+                f,
+                "{} must be a UNIX timestamp, got \"{}\"",
+                SOURCE_DATE_EPOCH, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CurrentYearErr {}
+
+/// Gets the time that counts as "now", which is `SOURCE_DATE_EPOCH` if it's
+/// set, or else the system clock.
+pub fn now() -> Result<DateTime<Utc>, CurrentYearErr> {
+    now_at(env::var(SOURCE_DATE_EPOCH).ok())
+}
+
+fn now_at(source_date_epoch: Option<String>) -> Result<DateTime<Utc>, CurrentYearErr> {
+    match source_date_epoch {
+        Some(value) if !value.trim().is_empty() => {
+            let timestamp = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| CurrentYearErr::InvalidSourceDateEpoch(value.clone()))?;
+            match Utc.timestamp_opt(timestamp, 0).single() {
+                Some(date) => Ok(date),
+                None => Err(CurrentYearErr::InvalidSourceDateEpoch(value)),
+            }
+        }
+        _ => Ok(Utc::now()),
+    }
+}
+
+/// Gets the year that counts as "now". A `--year` override wins, then
+/// `SOURCE_DATE_EPOCH`, then the system clock.
+pub fn current_year(year_override: Option<i32>) -> Result<i32, CurrentYearErr> {
+    current_year_at(year_override, env::var(SOURCE_DATE_EPOCH).ok())
+}
+
+fn current_year_at(
+    year_override: Option<i32>,
+    source_date_epoch: Option<String>,
+) -> Result<i32, CurrentYearErr> {
+    if let Some(year) = year_override {
+        return Ok(year);
+    }
+
+    Ok(now_at(source_date_epoch)?.year())
+}
+
+/// Gets today's date as `YYYY-MM-DD`.
+pub fn current_date() -> Result<String, CurrentYearErr> {
+    Ok(now()?.format("%Y-%m-%d").to_string())
+}
+
+/// Formats a span of years, collapsing it to a single year when both ends are
+/// the same.
+pub fn format_year_range(first: i32, last: i32) -> String {
+    if first >= last {
+        first.to_string()
+    } else {
+        format!("{}-{}", first, last)
+    }
+}
+
+/// Gets the year a policy writes into `%YEAR%`, given the year the file was
+/// created and the current year. `Preserve` starts from the current year and
+/// takes the year of an existing header once the file is read.
+pub fn policy_year(policy: YearPolicy, created_year: i32, year: i32) -> String {
+    match policy {
+        YearPolicy::Fixed(year) => year.to_string(),
+        YearPolicy::Creation => created_year.to_string(),
+        YearPolicy::Range => format_year_range(created_year, year),
+        YearPolicy::Current | YearPolicy::Preserve => year.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2001-09-09T01:46:40Z
+    const EPOCH: &str = "1000000000";
+
+    #[test]
+    fn reads_source_date_epoch() {
+        let date = now_at(Some(EPOCH.to_string())).unwrap();
+        assert_eq!(date.format("%Y-%m-%d").to_string(), "2001-09-09");
+        assert_eq!(now_at(Some(format!(" {}\n", EPOCH))).unwrap(), date);
+    }
+
+    #[test]
+    fn uses_the_clock_without_source_date_epoch() {
+        let year = Utc::now().year();
+        assert!(now_at(None).unwrap().year() >= year);
+        assert!(now_at(Some(String::from("  "))).unwrap().year() >= year);
+    }
+
+    #[test]
+    fn rejects_invalid_source_date_epoch() {
+        for value in ["yesterday", "1.5", "99999999999999999"] {
+            match now_at(Some(value.to_string())) {
+                Err(CurrentYearErr::InvalidSourceDateEpoch(v)) => assert_eq!(v, value),
+                Ok(date) => panic!("{} was read as {}", value, date),
+            }
+        }
+    }
+
+    #[test]
+    fn prefers_the_year_override_to_source_date_epoch() {
+        assert_eq!(
+            current_year_at(Some(1999), Some(EPOCH.to_string())).unwrap(),
+            1999
+        );
+        assert_eq!(
+            current_year_at(None, Some(EPOCH.to_string())).unwrap(),
+            2001
+        );
+        // An override doesn't need a valid SOURCE_DATE_EPOCH
+        assert_eq!(
+            current_year_at(Some(1999), Some(String::from("x"))).unwrap(),
+            1999
+        );
+    }
+
+    #[test]
+    fn formats_year_ranges() {
+        assert_eq!(format_year_range(2019, 2025), "2019-2025");
+        assert_eq!(format_year_range(2025, 2025), "2025");
+        // A file can't be created after the current year
+        assert_eq!(format_year_range(2026, 2025), "2026");
+    }
+
+    #[test]
+    fn picks_the_year_for_each_policy() {
+        assert_eq!(policy_year(YearPolicy::Current, 2019, 2025), "2025");
+        assert_eq!(policy_year(YearPolicy::Fixed(2010), 2019, 2025), "2010");
+        assert_eq!(policy_year(YearPolicy::Creation, 2019, 2025), "2019");
+        assert_eq!(policy_year(YearPolicy::Range, 2019, 2025), "2019-2025");
+        assert_eq!(policy_year(YearPolicy::Range, 2025, 2025), "2025");
+        assert_eq!(policy_year(YearPolicy::Preserve, 2019, 2025), "2025");
+    }
+
+    #[test]
+    fn parses_policies() {
+        let parse = |json: &str| serde_json::from_str::<YearPolicy>(json).ok();
+        assert_eq!(parse("\"current\""), Some(YearPolicy::Current));
+        assert_eq!(parse("2010"), Some(YearPolicy::Fixed(2010)));
+        assert_eq!(parse("\"creation\""), Some(YearPolicy::Creation));
+        assert_eq!(parse("\"range\""), Some(YearPolicy::Range));
+        assert_eq!(parse("\"first-last\""), Some(YearPolicy::Range));
+        assert_eq!(parse("\"preserve\""), Some(YearPolicy::Preserve));
+        assert_eq!(parse("\"latest\""), None);
+    }
+}